@@ -0,0 +1,47 @@
+#![feature(test)]
+
+extern crate strutters;
+extern crate test;
+
+use strutters::tree::IterativeSegmentTree;
+use strutters::tree::SegmentTree;
+use test::Bencher;
+
+const LENGTH: usize = 1 << 16;
+
+/// A cheap deterministic sequence of query ranges
+fn ranges() -> impl Iterator<Item=(usize, usize)> {
+	(0..1024).map(|index: usize| {
+		let left = index.wrapping_mul(7919) % LENGTH;
+		let right = index.wrapping_mul(104_729) % LENGTH;
+		if left <= right { (left, right) } else { (right, left) }
+	})
+}
+
+#[bench]
+fn recursive_query(bencher: &mut Bencher) {
+	let tree = SegmentTree::construct_implicit((0..LENGTH as u64).collect::<Vec<_>>().into_iter(),
+	                                           strutters::operator::summation());
+	bencher.iter(|| ranges().map(|(left, right)| *tree.query(left..=right)).sum::<u64>());
+}
+
+#[bench]
+fn iterative_query(bencher: &mut Bencher) {
+	let tree = IterativeSegmentTree::construct((0..LENGTH as u64).collect::<Vec<_>>().into_iter(),
+	                                           strutters::operator::summation());
+	bencher.iter(|| ranges().map(|(left, right)| *tree.query(left..=right)).sum::<u64>());
+}
+
+#[bench]
+fn recursive_update(bencher: &mut Bencher) {
+	let mut tree = SegmentTree::construct_implicit((0..LENGTH as u64).collect::<Vec<_>>().into_iter(),
+	                                               strutters::operator::summation());
+	bencher.iter(|| ranges().for_each(|(left, right)| tree.update(left, right as u64)));
+}
+
+#[bench]
+fn iterative_update(bencher: &mut Bencher) {
+	let mut tree = IterativeSegmentTree::construct((0..LENGTH as u64).collect::<Vec<_>>().into_iter(),
+	                                               strutters::operator::summation());
+	bencher.iter(|| ranges().for_each(|(left, right)| tree.update(left, right as u64)));
+}
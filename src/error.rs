@@ -0,0 +1,28 @@
+use core::fmt;
+
+/// Errors returned by the fallible variants of queries and updates
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+	/// The start of a range is after its end
+	InvalidRange { start: usize, end: usize },
+	/// An index or the end of a range is beyond the length of the data
+	OutOfBounds { index: usize, length: usize },
+	/// The range is empty and there is no identity to return
+	EmptyRange,
+	/// A constraint contradicts the constraints that were already added
+	Conflict,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match self {
+			Error::InvalidRange { start, end } => write!(f, "range start {} is after range end {}", start, end),
+			Error::OutOfBounds { index, length } => write!(f, "index {} is out of bounds for length {}", index, length),
+			Error::EmptyRange => write!(f, "operator has no identity for an empty range"),
+			Error::Conflict => write!(f, "constraint conflicts with an existing constraint"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Error {}
@@ -4,8 +4,14 @@ use super::Graph;
 use super::MutableGraph;
 use Vec;
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+	serialize = "E: ::serde::Serialize, E::Node: ::serde::Serialize",
+	deserialize = "E: ::serde::Deserialize<'de>, E::Node: ::serde::Deserialize<'de>"
+)))]
 pub struct AdjacencyList<E> where E: Edge {
 	edges: BTreeMap<E::Node, Vec<E>>,
+	#[cfg_attr(feature = "serde", serde(skip))]
 	_empty: Vec<E>,
 }
 
@@ -36,4 +42,24 @@ impl<'g, E> MutableGraph<'g> for AdjacencyList<E> where E: 'g + Edge {
 	fn add_edge(&mut self, start: E::Node, edge: E) {
 		self.edges.entry(start).or_insert(Vec::new()).push(edge);
 	}
-}
\ No newline at end of file
+}
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+	use super::*;
+	use graph::HalfEdge;
+	use graph::WeightedEdge;
+
+	#[test]
+	fn test_serde() {
+		let mut graph = AdjacencyList::new();
+		graph.add_edge(1, HalfEdge::new(2, 5));
+		graph.add_edge(1, HalfEdge::new(3, 7));
+		graph.add_edge(3, HalfEdge::new(1, 4));
+		let serialized = ::serde_json::to_string(&graph).unwrap();
+		let graph: AdjacencyList<HalfEdge<u32, u32>> = ::serde_json::from_str(&serialized).unwrap();
+		assert!(graph.nodes().eq([1, 3].iter()));
+		let edges: Vec<(u32, u32)> = graph.neighbours(&1).map(|edge| (*edge.end_node(), *edge.weight())).collect();
+		assert_eq!(edges, vec![(2, 5), (3, 7)]);
+		assert_eq!(graph.neighbours(&2).count(), 0);
+	}
+}
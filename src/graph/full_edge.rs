@@ -5,6 +5,7 @@ use super::ReversibleEdge;
 use super::Weight;
 use super::WeightedEdge;
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FullEdge<N, W> {
 	start_node: N,
 	edge: HalfEdge<N, W>,
@@ -56,4 +57,17 @@ impl<N, W> CompleteEdge for FullEdge<N, W> where N: Ord {
 	fn start_node(&self) -> &<Self as Edge>::Node {
 		&self.start_node
 	}
-}
\ No newline at end of file
+}
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_serde() {
+		let serialized = ::serde_json::to_string(&FullEdge::new('a', 'b', 3)).unwrap();
+		let edge: FullEdge<char, u32> = ::serde_json::from_str(&serialized).unwrap();
+		assert_eq!(edge.start_node(), &'a');
+		assert_eq!(edge.end_node(), &'b');
+		assert_eq!(edge.weight(), &3);
+	}
+}
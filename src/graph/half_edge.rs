@@ -3,6 +3,7 @@ use super::ReversibleEdge;
 use super::Weight;
 use super::WeightedEdge;
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HalfEdge<N, W> {
 	end_node: N,
 	weight: W,
@@ -49,3 +50,16 @@ impl<N, W> ReversibleEdge for HalfEdge<N, W> where N: Ord + Clone, W: Clone {
 		(end_node.clone(), HalfEdge::new(start.clone(), weight))
 	}
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_serde() {
+		let serialized = ::serde_json::to_string(&HalfEdge::new('b', 3)).unwrap();
+		let edge: HalfEdge<char, u32> = ::serde_json::from_str(&serialized).unwrap();
+		assert_eq!(edge.end_node(), &'b');
+		assert_eq!(edge.weight(), &3);
+	}
+}
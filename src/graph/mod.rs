@@ -14,6 +14,7 @@ pub use self::undirected_graph::UndirectedGraph;
 pub mod spanning_tree;
 pub mod traversal;
 pub mod shortest_path;
+pub mod rooted_tree;
 mod graph;
 mod edge;
 mod adjacency_list;
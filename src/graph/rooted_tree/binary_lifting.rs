@@ -0,0 +1,398 @@
+use collections::BTreeMap;
+use graph::Edge;
+use graph::Graph;
+use math::integer_log2;
+use operator::Monoid;
+use OwnedRef;
+use super::indexed_tree::IndexedTree;
+use Vec;
+
+/// Stores the ancestors of every node at each power of two distance
+///
+/// Any ancestor can be reached by combining at most O(`log n`) jumps.
+/// Values can be attached to the nodes with `with_values` so that the
+/// values along each jump are combined ahead of time:
+/// - Construct: O(`n log n`)
+/// - K-th ancestor: O(`log n`)
+/// - First ancestor satisfying a predicate: O(`log n`)
+/// - Lowest common ancestor and path query: O(`log n`)
+///
+/// Path queries combine the values in order from the first node to the
+/// second. The values along each jump are also combined from the top so
+/// that the part of the path above the second node can be walked downwards.
+pub struct BinaryLifting<N, T = (), O = fn(&(), &())> {
+	indexes: BTreeMap<N, usize>,
+	nodes: Vec<N>,
+	depths: Vec<usize>,
+	/// The ancestor `2^level` edges above each node
+	ancestors: Vec<Vec<Option<usize>>>,
+	/// The combined values of the `2^level` nodes starting at each node and going upwards,
+	/// empty until values are attached
+	aggregates: Vec<Vec<Option<T>>>,
+	/// The same values combined downwards, empty at level zero as single values have no order
+	descending_aggregates: Vec<Vec<Option<T>>>,
+	operator: O,
+}
+
+impl<N> BinaryLifting<N> where N: Ord + Clone {
+	/// Finds the ancestors of every node in the tree reachable from `root`
+	pub fn construct<'g, G>(graph: &'g G, root: &N) -> BinaryLifting<N> where G: Graph<'g>, G::Edge: Edge<Node=N> {
+		let IndexedTree { indexes, nodes, parents, .. } = IndexedTree::construct(graph, root, |_, _| ());
+		BinaryLifting::construct_parents(indexes, nodes, parents)
+	}
+}
+
+impl BinaryLifting<usize> {
+	/// Finds the ancestors of every node in a forest where `parents[node]` is the parent of `node`
+	///
+	/// Roots have no parent.
+	///
+	/// # Panics
+	///
+	/// Every parent must be less than the number of nodes
+	/// The parents cannot form a cycle
+	pub fn from_parents(parents: &[Option<usize>]) -> BinaryLifting<usize> {
+		let indexes = (0..parents.len()).map(|node| (node, node)).collect();
+		BinaryLifting::construct_parents(indexes, (0..parents.len()).collect(), parents.to_vec())
+	}
+}
+
+impl<N> BinaryLifting<N> {
+	fn construct_parents(indexes: BTreeMap<N, usize>, nodes: Vec<N>, parents: Vec<Option<usize>>) -> BinaryLifting<N> {
+		let length = nodes.len();
+		let mut depths: Vec<Option<usize>> = vec![None; length];
+		let mut stack = Vec::new();
+		for node in 0..length {
+			// Walk up until a node with a known depth is found
+			let mut current = node;
+			while depths[current].is_none() {
+				stack.push(current);
+				assert!(stack.len() <= length, "parents form a cycle");
+				match parents[current] {
+					Some(parent) => current = parent,
+					None => break,
+				}
+			}
+
+			let mut depth = depths[current].map_or(0, |depth| depth + 1);
+			while let Some(node) = stack.pop() {
+				depths[node] = Some(depth);
+				depth += 1;
+			}
+		}
+
+		let depths: Vec<usize> = depths.into_iter().map(|depth| depth.unwrap()).collect();
+		let max_depth = depths.iter().cloned().max().unwrap_or(0);
+		let mut ancestors = vec![parents];
+		for level in 1..integer_log2(max_depth.max(1) as u64) as usize + 1 {
+			let previous = &ancestors[level - 1];
+			let next = previous.iter().map(|ancestor| ancestor.and_then(|ancestor| previous[ancestor])).collect();
+			ancestors.push(next);
+		}
+
+		BinaryLifting {
+			indexes,
+			nodes,
+			depths,
+			ancestors,
+			aggregates: Vec::new(),
+			descending_aggregates: Vec::new(),
+			operator: combine_nothing as fn(&(), &()),
+		}
+	}
+
+	/// Attaches a value to every node
+	///
+	/// `value` creates the value of each node. Values of edges such as
+	/// weights should be given to the lower node of each edge.
+	pub fn with_values<T, O, F>(self, value: F, operator: O) -> BinaryLifting<N, T, O>
+		where O: Monoid<T>, F: FnMut(&N) -> T {
+		let values = self.nodes.iter().map(value).collect();
+		let mut lifting = BinaryLifting {
+			indexes: self.indexes,
+			nodes: self.nodes,
+			depths: self.depths,
+			ancestors: self.ancestors,
+			aggregates: Vec::new(),
+			descending_aggregates: Vec::new(),
+			operator,
+		};
+		lifting.compute_aggregates(values);
+		lifting
+	}
+}
+
+impl<N, T, O> BinaryLifting<N, T, O> where O: Monoid<T> {
+	fn compute_aggregates(&mut self, values: Vec<T>) {
+		self.aggregates = vec![values.into_iter().map(Some).collect()];
+		self.descending_aggregates = vec![Vec::new()];
+		for level in 1..self.ancestors.len() {
+			let next = (0..self.nodes.len()).map(|node| {
+				let middle = self.ancestors[level - 1][node]?;
+				let (lower, upper) = (self.aggregate(level - 1, node, false)?, self.aggregate(level - 1, middle, false)?);
+				Some(self.operator.operate(lower, upper))
+			}).collect();
+
+			let descending = (0..self.nodes.len()).map(|node| {
+				let middle = self.ancestors[level - 1][node]?;
+				let (lower, upper) = (self.aggregate(level - 1, node, true)?, self.aggregate(level - 1, middle, true)?);
+				Some(self.operator.operate(upper, lower))
+			}).collect();
+
+			self.aggregates.push(next);
+			self.descending_aggregates.push(descending);
+		}
+	}
+
+	/// Returns the combined values of the `2^level` nodes starting at `node`
+	fn aggregate(&self, level: usize, node: usize, descending: bool) -> Option<&T> {
+		match descending && level > 0 {
+			true => self.descending_aggregates[level][node].as_ref(),
+			false => self.aggregates[level][node].as_ref(),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// Returns the number of edges between `node` and its root
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn depth(&self, node: &N) -> usize where N: Ord {
+		self.depths[self.index(node)]
+	}
+
+	/// Finds the ancestor `k` edges above `node`
+	///
+	/// Returns `None` if `k` is greater than the depth of `node`.
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn kth_ancestor(&self, node: &N, k: usize) -> Option<&N> where N: Ord {
+		let node = self.index(node);
+		if k > self.depths[node] { return None; }
+		Some(&self.nodes[self.jump(node, k)])
+	}
+
+	/// Finds the nearest ancestor of `node` that satisfies `predicate`, starting with `node` itself
+	///
+	/// The predicate must be monotone: if an ancestor satisfies it then so
+	/// must every ancestor above it.
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn first_ancestor<F>(&self, node: &N, predicate: F) -> Option<&N> where N: Ord, F: Fn(&N) -> bool {
+		let mut current = self.index(node);
+		if predicate(&self.nodes[current]) { return Some(&self.nodes[current]); }
+		for level in (0..self.ancestors.len()).rev() {
+			if let Some(ancestor) = self.ancestors[level][current] {
+				if !predicate(&self.nodes[ancestor]) { current = ancestor; }
+			}
+		}
+
+		self.ancestors[0][current].map(|parent| &self.nodes[parent]).filter(|parent| predicate(parent))
+	}
+
+	/// Finds the lowest common ancestor of two nodes
+	///
+	/// Returns `None` if the nodes are in different trees.
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn lca(&self, a: &N, b: &N) -> Option<&N> where N: Ord {
+		self.lca_index(self.index(a), self.index(b)).map(|lca| &self.nodes[lca])
+	}
+
+	/// Combines the values of every node on the path between two nodes
+	///
+	/// Returns `None` if the nodes are in different trees.
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	/// Values must have been attached with `with_values`
+	pub fn query_path(&self, a: &N, b: &N) -> Option<OwnedRef<'_, T>> where N: Ord {
+		self.query_path_values(a, b, false)
+	}
+
+	/// Combines the values of every edge on the path between two nodes
+	///
+	/// The value of an edge is held by its lower node. Returns `None` if
+	/// the nodes are in different trees.
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	/// Values must have been attached with `with_values`
+	/// The operator must have an identity if the nodes are equal
+	pub fn query_path_edges(&self, a: &N, b: &N) -> Option<OwnedRef<'_, T>> where N: Ord {
+		self.query_path_values(a, b, true)
+	}
+
+	fn query_path_values(&self, a: &N, b: &N, edges: bool) -> Option<OwnedRef<'_, T>> where N: Ord {
+		assert!(!self.aggregates.is_empty(), "no values have been attached");
+		let (a, b) = (self.index(a), self.index(b));
+		let lca = self.lca_index(a, b)?;
+		let (mut upwards, mut downwards) = (None, None);
+		self.climb(a, self.depths[a] - self.depths[lca], &mut upwards, false);
+		if !edges {
+			self.combine(&mut upwards, self.aggregate(0, lca, false).unwrap(), false);
+		}
+		self.climb(b, self.depths[b] - self.depths[lca], &mut downwards, true);
+
+		let result = match (upwards, downwards) {
+			(Some(upwards), Some(downwards)) => Some(self.operator.operate(&upwards, &downwards).into()),
+			(upwards, downwards) => upwards.or(downwards),
+		};
+
+		Some(result.unwrap_or_else(|| {
+			self.operator.identity().expect("operator has no identity for an empty path").into()
+		}))
+	}
+
+	/// Moves `steps` edges above `node`
+	fn jump(&self, mut node: usize, steps: usize) -> usize {
+		for level in 0..self.ancestors.len() {
+			if steps & (1 << level) != 0 {
+				node = self.ancestors[level][node].unwrap();
+			}
+		}
+		node
+	}
+
+	/// Moves `steps` edges above `node` while combining the values of the nodes that are left
+	///
+	/// The values are combined downwards towards `node` if `descending` is set.
+	fn climb<'a>(&'a self, mut node: usize, steps: usize, result: &mut Option<OwnedRef<'a, T>>,
+	             descending: bool) -> usize {
+		for level in 0..self.ancestors.len() {
+			if steps & (1 << level) != 0 {
+				self.combine(result, self.aggregate(level, node, descending).unwrap(), descending);
+				node = self.ancestors[level][node].unwrap();
+			}
+		}
+		node
+	}
+
+	/// Combines `value` after the result or before it if `prepend` is set
+	fn combine<'a>(&'a self, result: &mut Option<OwnedRef<'a, T>>, value: &'a T, prepend: bool) {
+		*result = Some(match result.take() {
+			Some(result) if prepend => self.operator.operate(value, &result).into(),
+			Some(result) => self.operator.operate(&result, value).into(),
+			None => value.into(),
+		});
+	}
+
+	fn lca_index(&self, mut a: usize, mut b: usize) -> Option<usize> {
+		if self.depths[a] < self.depths[b] { ::core::mem::swap(&mut a, &mut b); }
+		a = self.jump(a, self.depths[a] - self.depths[b]);
+		if a == b { return Some(a); }
+
+		for level in (0..self.ancestors.len()).rev() {
+			if self.ancestors[level][a] != self.ancestors[level][b] {
+				a = self.ancestors[level][a].unwrap();
+				b = self.ancestors[level][b].unwrap();
+			}
+		}
+		self.ancestors[0][a]
+	}
+
+	fn index(&self, node: &N) -> usize where N: Ord {
+		*self.indexes.get(node).expect("node is not in the tree")
+	}
+}
+
+/// The operator used before any values are attached
+fn combine_nothing(_: &(), _: &()) {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parents() {
+		use graph::rooted_tree::fixture;
+
+		// A second tree with the nodes 12 and 13 makes a forest
+		let mut parents = fixture::PARENTS.to_vec();
+		parents.extend([12, 12].iter());
+		let optional_parents: Vec<Option<usize>> = parents.iter().enumerate()
+			.map(|(node, parent)| if node == *parent { None } else { Some(*parent) }).collect();
+		let lifting = BinaryLifting::from_parents(&optional_parents)
+			.with_values(|node| fixture::weight(*node), ::operator::summation_default());
+
+		for a in 0..parents.len() {
+			let path_a = fixture::ancestors(&parents, a);
+			assert_eq!(lifting.depth(&a), path_a.len() - 1);
+			for k in 0..6 {
+				assert_eq!(lifting.kth_ancestor(&a, k), path_a.get(k));
+			}
+
+			let shallow = |node: &usize| fixture::ancestors(&parents, *node).len() <= 2;
+			assert_eq!(lifting.first_ancestor(&a, shallow), path_a.iter().find(|node| shallow(node)));
+
+			for b in 0..parents.len() {
+				let lca = fixture::lca(&parents, a, b);
+				assert_eq!(lifting.lca(&a, &b), lca.as_ref());
+
+				let expected = fixture::path(&parents, a, b).map(|path| path.into_iter().map(fixture::weight).sum::<u64>());
+				assert_eq!(lifting.query_path(&a, &b).map(|value| *value), expected);
+				assert_eq!(lifting.query_path_edges(&a, &b).map(|value| *value),
+				           expected.map(|value| value - fixture::weight(lca.unwrap())));
+			}
+		}
+	}
+
+	#[test]
+	fn test_path_order() {
+		use graph::rooted_tree::fixture;
+
+		// Digits are concatenated so the direction of every query is visible
+		let operator = |a: &(u64, u64), b: &(u64, u64)| (a.0 * b.1 + b.0, a.1 * b.1);
+		let digit = |node: usize| (node as u64 % 9 + 1, 10);
+		let parents = fixture::PARENTS;
+		let operator_with_identity = ::operator::with_identity(operator, ::provider::ClosureProvider::from(|| (0, 1)));
+		let lifting = BinaryLifting::construct(&fixture::graph(&parents), &0)
+			.with_values(|node| digit(*node), operator_with_identity);
+
+		for a in 0..parents.len() {
+			for b in 0..parents.len() {
+				let lca = fixture::lca(&parents, a, b).unwrap();
+				let path = fixture::path(&parents, a, b).unwrap();
+				let expected = path.iter().fold((0, 1), |aggregate, node| operator(&aggregate, &digit(*node)));
+				assert_eq!(lifting.query_path(&a, &b).map(|value| *value), Some(expected));
+
+				let edges = path.into_iter().filter(|node| *node != lca);
+				let expected = edges.fold((0, 1), |aggregate, node| operator(&aggregate, &digit(node)));
+				assert_eq!(lifting.query_path_edges(&a, &b).map(|value| *value), Some(expected));
+			}
+		}
+	}
+
+	#[test]
+	fn test_graph() {
+		use graph::AdjacencyList;
+		use graph::HalfEdge;
+		use graph::MutableGraph;
+
+		let mut graph = AdjacencyList::new();
+		for (parent, child) in ['a', 'b', 'c', 'd'].iter().zip(['b', 'c', 'd', 'e'].iter()) {
+			graph.add_edge(*parent, HalfEdge::new_weightless(*child));
+		}
+
+		let lifting = BinaryLifting::construct(&graph, &'a');
+		assert_eq!(lifting.kth_ancestor(&'e', 3), Some(&'b'));
+		assert_eq!(lifting.first_ancestor(&'e', |node| *node <= 'c'), Some(&'c'));
+		assert_eq!(lifting.lca(&'b', &'d'), Some(&'b'));
+	}
+}
@@ -0,0 +1,205 @@
+use collections::BTreeMap;
+use graph::Edge;
+use graph::Graph;
+use super::indexed_tree::IndexedTree;
+use Vec;
+
+/// Recursively splits a tree at its centroids
+///
+/// The centroid of a tree is a node whose removal leaves no component with
+/// more than half of the nodes. The centroid of the whole tree becomes the
+/// root of the centroid tree and the centroids of the remaining components
+/// become its children. The centroid tree has a depth of O(`log n`) and the
+/// path between any two nodes passes through their lowest common centroid
+/// ancestor:
+/// - Construct: O(`n log n`)
+/// - Parent and depth: O(`log n`)
+/// - Walking the centroid ancestors: O(`log n`)
+///
+/// Distances to every centroid ancestor are counted in edges and are
+/// computed ahead of time so that aggregates can be kept per centroid.
+pub struct CentroidDecomposition<N> {
+	indexes: BTreeMap<N, usize>,
+	nodes: Vec<N>,
+	parents: Vec<Option<usize>>,
+	depths: Vec<usize>,
+	/// The distance from each node to its centroid ancestors starting with the root
+	distances: Vec<Vec<usize>>,
+	root: usize,
+}
+
+impl<N> CentroidDecomposition<N> where N: Ord + Clone {
+	/// Decomposes the tree reachable from `start`
+	///
+	/// The tree is no longer rooted at `start` once it has been found.
+	pub fn construct<'g, G>(graph: &'g G, start: &N) -> CentroidDecomposition<N>
+		where G: Graph<'g>, G::Edge: Edge<Node=N> {
+		let IndexedTree { indexes, nodes, parents, children, .. } = IndexedTree::construct(graph, start, |_, _| ());
+		let neighbours: Vec<Vec<usize>> = parents.into_iter().zip(children)
+			.map(|(parent, children)| parent.into_iter().chain(children).collect()).collect();
+
+		let length = nodes.len();
+		let mut decomposition = CentroidDecomposition {
+			indexes,
+			nodes,
+			parents: vec![None; length],
+			depths: vec![0; length],
+			distances: vec![Vec::new(); length],
+			root: 0,
+		};
+
+		let mut removed = vec![false; length];
+		let mut previous = vec![0; length];
+		let mut sizes = vec![0; length];
+		let mut distances = vec![0; length];
+		let mut components = vec![(0, None)];
+		while let Some((start, parent)) = components.pop() {
+			// Every node in the component follows the node it was reached from
+			let order = component_order(&neighbours, &removed, &mut previous, start);
+			for &node in order.iter().rev() {
+				sizes[node] = 1 + neighbours[node].iter()
+					.filter(|next| !removed[**next] && **next != previous[node] && previous[**next] == node)
+					.map(|next| sizes[*next]).sum::<usize>();
+			}
+
+			let total = order.len();
+			let mut centroid = start;
+			while let Some(&heavy) = neighbours[centroid].iter().find(|next| {
+				!removed[**next] && **next != previous[centroid] && sizes[**next] * 2 > total
+			}) {
+				centroid = heavy;
+			}
+
+			decomposition.parents[centroid] = parent;
+			decomposition.depths[centroid] = parent.map_or(0, |parent| decomposition.depths[parent] + 1);
+			if parent.is_none() { decomposition.root = centroid; }
+
+			let order = component_order(&neighbours, &removed, &mut previous, centroid);
+			for &node in order.iter() {
+				distances[node] = if node == centroid { 0 } else { distances[previous[node]] + 1 };
+				decomposition.distances[node].push(distances[node]);
+			}
+
+			removed[centroid] = true;
+			for &next in neighbours[centroid].iter().filter(|next| !removed[**next]) {
+				components.push((next, Some(centroid)));
+			}
+		}
+		decomposition
+	}
+
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// Returns the centroid of the whole tree
+	pub fn root(&self) -> &N {
+		&self.nodes[self.root]
+	}
+
+	/// Returns the parent of `node` in the centroid tree
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn parent(&self, node: &N) -> Option<&N> {
+		self.parents[self.index(node)].map(|parent| &self.nodes[parent])
+	}
+
+	/// Returns the number of edges between `node` and the root of the centroid tree
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn depth(&self, node: &N) -> usize {
+		self.depths[self.index(node)]
+	}
+
+	/// Walks from `node` to the root of the centroid tree
+	///
+	/// Each centroid ancestor is given with its distance to `node` in the
+	/// original tree. The first ancestor is `node` itself at distance zero.
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn ancestors<'a>(&'a self, node: &N) -> impl Iterator<Item=(&'a N, usize)> + 'a {
+		let node = self.index(node);
+		let distances = &self.distances[node];
+		let mut current = Some(node);
+		::core::iter::from_fn(move || {
+			let ancestor = current?;
+			current = self.parents[ancestor];
+			Some((&self.nodes[ancestor], distances[self.depths[ancestor]]))
+		})
+	}
+
+	fn index(&self, node: &N) -> usize {
+		*self.indexes.get(node).expect("node is not in the tree")
+	}
+}
+
+/// Lists the nodes reachable from `start` without crossing removed nodes
+///
+/// `previous` is set to the node each node was reached from.
+fn component_order(neighbours: &[Vec<usize>], removed: &[bool], previous: &mut [usize], start: usize) -> Vec<usize> {
+	let mut order = vec![start];
+	previous[start] = start;
+	let mut index = 0;
+	while index < order.len() {
+		let node = order[index];
+		for &next in neighbours[node].iter() {
+			if removed[next] || next == previous[node] { continue; }
+			previous[next] = node;
+			order.push(next);
+		}
+		index += 1;
+	}
+	order
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_nearest_marked() {
+		use graph::rooted_tree::fixture;
+
+		let parents = fixture::PARENTS;
+		let decomposition = CentroidDecomposition::construct(&fixture::graph(&parents), &0);
+		let distance = |a: usize, b: usize| fixture::path(&parents, a, b).unwrap().len() - 1;
+
+		assert_eq!(decomposition.parent(decomposition.root()), None);
+		for node in 0..parents.len() {
+			assert!(decomposition.depth(&node) <= 3);
+			let centroids: Vec<(usize, usize)> = decomposition.ancestors(&node).map(|(node, distance)| (*node, distance)).collect();
+			assert_eq!(centroids.len(), decomposition.depth(&node) + 1);
+			for &(centroid, centroid_distance) in centroids.iter() {
+				assert_eq!(centroid_distance, distance(node, centroid));
+			}
+		}
+
+		// Each centroid keeps the distance to its nearest marked node
+		let mut nearest = vec![usize::MAX; parents.len()];
+		let mut marked = Vec::new();
+		for &mark in [6, 11, 9].iter() {
+			marked.push(mark);
+			for (centroid, distance) in decomposition.ancestors(&mark) {
+				nearest[*centroid] = nearest[*centroid].min(distance);
+			}
+
+			for node in 0..parents.len() {
+				let expected = marked.iter().map(|mark| distance(node, *mark)).min().unwrap();
+				let found = decomposition.ancestors(&node).map(|(centroid, distance)| {
+					nearest[*centroid].saturating_add(distance)
+				}).min().unwrap();
+				assert_eq!(found, expected);
+			}
+		}
+	}
+}
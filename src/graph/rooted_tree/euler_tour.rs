@@ -0,0 +1,202 @@
+use collections::BTreeMap;
+use core::cmp::Reverse;
+use core::ops::Sub;
+use graph::Edge;
+use graph::Graph;
+use graph::Weight;
+use graph::WeightedEdge;
+use query::IndexSparseTable;
+use super::indexed_tree::IndexedTree;
+use Vec;
+
+/// The order in which a depth first traversal enters and leaves the nodes of a rooted tree
+///
+/// A node is recorded when it is first reached and again after each of its
+/// children is finished so the tour holds `2n - 1` entries. The shallowest
+/// entry between the first occurrences of two nodes is their lowest common
+/// ancestor. Use `lowest_common_ancestor` to answer these queries:
+/// - Construct: O(`n log n`)
+/// - Depth: O(`log n`)
+pub struct EulerTour<N, W = ()> {
+	indexes: BTreeMap<N, usize>,
+	nodes: Vec<N>,
+	depths: Vec<usize>,
+	weighted_depths: Vec<W>,
+	first: Vec<usize>,
+	tour: Vec<usize>,
+	/// Depths are reversed so that `IndexSparseTable` finds the shallowest entry
+	tour_depths: Vec<Reverse<usize>>,
+}
+
+impl<N> EulerTour<N> where N: Ord + Clone {
+	/// Traverses the tree reachable from `root`
+	pub fn construct<'g, G>(graph: &'g G, root: &N) -> EulerTour<N> where G: Graph<'g>, G::Edge: Edge<Node=N> {
+		EulerTour::construct_with(graph, root, (), |_, _| ())
+	}
+}
+
+impl<N, W> EulerTour<N, W> where N: Ord + Clone, W: Weight + Default {
+	/// Traverses the tree reachable from `root` and records the weighted depth of every node
+	pub fn construct_weighted<'g, G>(graph: &'g G, root: &N) -> EulerTour<N, W>
+		where G: Graph<'g>, G::Edge: WeightedEdge<Node=N, Weight=W> {
+		EulerTour::construct_with(graph, root, W::default(), |depth, edge| W::combine(depth, edge.weight()))
+	}
+}
+
+impl<N, W> EulerTour<N, W> where N: Ord + Clone {
+	fn construct_with<'g, G, F>(graph: &'g G, root: &N, root_depth: W, weighted_depth: F) -> EulerTour<N, W>
+		where G: Graph<'g>, G::Edge: Edge<Node=N>, F: Fn(&W, &G::Edge) -> W {
+		let mut weighted_depths = vec![root_depth];
+		let IndexedTree { indexes, nodes, depths, children, .. } = IndexedTree::construct(graph, root, |parent, edge| {
+			let depth = weighted_depth(&weighted_depths[parent], edge);
+			weighted_depths.push(depth);
+		});
+
+		let length = nodes.len();
+		let mut tour = EulerTour {
+			indexes,
+			nodes,
+			depths,
+			weighted_depths,
+			first: vec![0; length],
+			tour: Vec::new(),
+			tour_depths: Vec::new(),
+		};
+
+		// Each node is paired with the number of its children that have been entered
+		let mut stack = vec![(0, 0)];
+		tour.visit(0);
+		while let Some((node, entered)) = stack.pop() {
+			match children[node].get(entered) {
+				Some(&child) => {
+					stack.push((node, entered + 1));
+					tour.first[child] = tour.tour.len();
+					tour.visit(child);
+					stack.push((child, 0));
+				}
+				None => if let Some(&(parent, _)) = stack.last() {
+					tour.visit(parent);
+				},
+			}
+		}
+		tour
+	}
+
+	fn visit(&mut self, node: usize) {
+		self.tour.push(node);
+		self.tour_depths.push(Reverse(self.depths[node]));
+	}
+
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// Returns the nodes in the order they are entered and left
+	pub fn tour(&self) -> impl Iterator<Item=&N> {
+		self.tour.iter().map(move |node| &self.nodes[*node])
+	}
+
+	/// Returns the number of edges between `node` and the root
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn depth(&self, node: &N) -> usize {
+		self.depths[self.index(node)]
+	}
+
+	/// Prepares a sparse table over the tour to answer queries in constant time
+	pub fn lowest_common_ancestor(&self) -> LowestCommonAncestor<'_, N, W> {
+		LowestCommonAncestor {
+			tour: self,
+			table: IndexSparseTable::compute(&self.tour_depths),
+		}
+	}
+
+	fn index(&self, node: &N) -> usize {
+		*self.indexes.get(node).expect("node is not in the tree")
+	}
+}
+
+/// Answers lowest common ancestor queries over an `EulerTour`
+///
+/// - Construct: O(`n log n`)
+/// - Lowest common ancestor: O(`log n`) to find the nodes and O(`1`) for the query
+/// - Distance: same as lowest common ancestor
+pub struct LowestCommonAncestor<'t, N, W> where N: 't, W: 't {
+	tour: &'t EulerTour<N, W>,
+	table: IndexSparseTable<'t, Reverse<usize>>,
+}
+
+impl<'t, N, W> LowestCommonAncestor<'t, N, W> where N: Ord + Clone {
+	/// Finds the lowest common ancestor of two nodes
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn lca(&self, a: &N, b: &N) -> &'t N {
+		&self.tour.nodes[self.lca_index(a, b)]
+	}
+
+	/// Returns the number of edges on the path between two nodes
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn distance(&self, a: &N, b: &N) -> usize {
+		let depths = &self.tour.depths;
+		let lca = self.lca_index(a, b);
+		depths[self.tour.index(a)] + depths[self.tour.index(b)] - 2 * depths[lca]
+	}
+
+	/// Returns the total weight of the edges on the path between two nodes
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn weighted_distance(&self, a: &N, b: &N) -> W where W: Weight + Sub<Output=W> + Clone {
+		let depths = &self.tour.weighted_depths;
+		let lca = depths[self.lca_index(a, b)].clone();
+		let (a, b) = (&depths[self.tour.index(a)], &depths[self.tour.index(b)]);
+		W::combine(&(a.clone() - lca.clone()), &(b.clone() - lca))
+	}
+
+	fn lca_index(&self, a: &N, b: &N) -> usize {
+		let (a, b) = (self.tour.first[self.tour.index(a)], self.tour.first[self.tour.index(b)]);
+		let (left, right) = if a <= b { (a, b) } else { (b, a) };
+		self.tour.tour[self.table.query_index(left..=right)]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_distances() {
+		use graph::rooted_tree::fixture;
+
+		let parents = fixture::PARENTS;
+		let tour = EulerTour::construct_weighted(&fixture::graph(&parents), &0);
+		assert_eq!(tour.tour().count(), 2 * parents.len() - 1);
+		let lca = tour.lowest_common_ancestor();
+
+		for a in 0..parents.len() {
+			assert_eq!(tour.depth(&a), fixture::ancestors(&parents, a).len() - 1);
+			for b in 0..parents.len() {
+				let expected = fixture::lca(&parents, a, b).unwrap();
+				assert_eq!(lca.lca(&a, &b), &expected);
+
+				// The weight of every edge is held by its lower node
+				let path = fixture::path(&parents, a, b).unwrap();
+				let edges: Vec<usize> = path.into_iter().filter(|node| *node != expected).collect();
+				assert_eq!(lca.distance(&a, &b), edges.len());
+				assert_eq!(lca.weighted_distance(&a, &b), edges.into_iter().map(fixture::weight).sum::<u64>());
+			}
+		}
+	}
+}
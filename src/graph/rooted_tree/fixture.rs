@@ -0,0 +1,51 @@
+use graph::AdjacencyList;
+use graph::HalfEdge;
+use graph::MutableGraph;
+use graph::UndirectedGraph;
+use Vec;
+
+/// The parent of every node in the tree used by the tests where the root is its own parent
+pub const PARENTS: [usize; 12] = [0, 0, 0, 1, 1, 2, 3, 3, 5, 8, 8, 4];
+
+/// The weight of the edge between `node` and its parent
+pub fn weight(node: usize) -> u64 {
+	(node as u64 * 7) % 5 + 1
+}
+
+/// Builds a graph with an edge in both directions between every node and its parent
+pub fn graph(parents: &[usize]) -> UndirectedGraph<AdjacencyList<HalfEdge<usize, u64>>> {
+	let mut graph = UndirectedGraph::new(AdjacencyList::new());
+	for (node, parent) in parents.iter().enumerate().filter(|(node, parent)| node != *parent) {
+		graph.add_edge(*parent, HalfEdge::new(node, weight(node)));
+	}
+	graph
+}
+
+/// Lists `node` followed by each of its ancestors up to its root
+pub fn ancestors(parents: &[usize], mut node: usize) -> Vec<usize> {
+	let mut ancestors = vec![node];
+	while parents[node] != node {
+		node = parents[node];
+		ancestors.push(node);
+	}
+	ancestors
+}
+
+/// Finds the lowest common ancestor of two nodes
+///
+/// Returns `None` if the nodes are in different trees.
+pub fn lca(parents: &[usize], a: usize, b: usize) -> Option<usize> {
+	let path_b = ancestors(parents, b);
+	ancestors(parents, a).into_iter().find(|node| path_b.contains(node))
+}
+
+/// Lists the nodes on the path from `a` to `b` in order
+///
+/// Returns `None` if the nodes are in different trees.
+pub fn path(parents: &[usize], a: usize, b: usize) -> Option<Vec<usize>> {
+	let lca = lca(parents, a, b)?;
+	let mut path: Vec<usize> = ancestors(parents, a).into_iter().take_while(|node| *node != lca).collect();
+	path.push(lca);
+	path.extend(ancestors(parents, b).into_iter().take_while(|node| *node != lca).collect::<Vec<_>>().into_iter().rev());
+	Some(path)
+}
@@ -0,0 +1,329 @@
+use collections::BTreeMap;
+use core::mem::swap;
+use core::ops::Range;
+use graph::Edge;
+use graph::Graph;
+use operator::Monoid;
+use OwnedRef;
+use super::indexed_tree::IndexedTree;
+use tree::DeltaSifter;
+use tree::DeltaWrapper;
+use tree::ImplicitTree;
+use tree::LazySegmentTree;
+use Vec;
+
+/// A `LazySegmentTree` over the positions of the nodes
+type PositionTree<T, O, S, D> = LazySegmentTree<T, ImplicitTree<DeltaWrapper<T, D>>, O, S, D>;
+
+/// Splits a rooted tree into paths so that it can be queried with a `LazySegmentTree`
+///
+/// Nodes are placed in the segment tree so that every subtree is a
+/// contiguous range and the path between any two nodes crosses at most
+/// O(`log n`) ranges:
+/// - Construct: O(`n log n`)
+/// - Lowest common ancestor: O(`log n`)
+/// - Path query and path update: O(`log² n`)
+/// - Subtree query and subtree update: O(`log n`)
+///
+/// Every node holds a single value. Values of edges such as weights are held
+/// by the lower node of each edge and the `_edges` variants of the path
+/// operations leave out the lowest common ancestor. Path queries combine the
+/// values in order from the first node to the second. The values are also
+/// kept in reverse order so that ranges walked upwards can be queried, which
+/// means every update is applied twice.
+pub struct HeavyLightDecomposition<N, T, O, S, D> {
+	tree: PositionTree<T, O, S, D>,
+	/// The values at each position starting from the last position
+	reversed: PositionTree<T, O, S, D>,
+	operator: O,
+	indexes: BTreeMap<N, usize>,
+	nodes: Vec<N>,
+	parents: Vec<Option<usize>>,
+	depths: Vec<usize>,
+	heads: Vec<usize>,
+	positions: Vec<usize>,
+	sizes: Vec<usize>,
+}
+
+impl<N, T, O, S, D> HeavyLightDecomposition<N, T, O, S, D>
+	where N: Ord + Clone, T: Clone, O: Monoid<T> + Clone, S: DeltaSifter<T, D> + Clone, D: Clone {
+	/// Decomposes the tree reachable from `root`
+	///
+	/// `value` creates the value of a node from the node and the edge from
+	/// its parent, which is `None` for the root.
+	pub fn construct<'g, G, F>(graph: &'g G, root: &N, mut value: F, operator: O, sifter: S)
+	                           -> HeavyLightDecomposition<N, T, O, S, D>
+		where G: Graph<'g>, G::Edge: Edge<Node=N>, F: FnMut(&N, Option<&G::Edge>) -> T {
+		let mut values = vec![value(root, None)];
+		let IndexedTree { indexes, nodes, parents, depths, children } =
+			IndexedTree::construct(graph, root, |_, edge| values.push(value(edge.end_node(), Some(edge))));
+
+		let length = nodes.len();
+		let mut sizes = vec![1; length];
+		for node in (1..length).rev() {
+			sizes[parents[node].unwrap()] += sizes[node];
+		}
+
+		// The heavy child is visited first so that it continues the path of its parent
+		let mut heads = vec![0; length];
+		let mut positions = vec![0; length];
+		let mut stack = vec![0];
+		let mut position = 0;
+		while let Some(node) = stack.pop() {
+			positions[node] = position;
+			position += 1;
+
+			let heavy = children[node].iter().cloned().max_by_key(|child| sizes[*child]);
+			for &child in children[node].iter().filter(|child| Some(**child) != heavy) {
+				heads[child] = child;
+				stack.push(child);
+			}
+			if let Some(heavy) = heavy {
+				heads[heavy] = heads[node];
+				stack.push(heavy);
+			}
+		}
+
+		let mut ordered: Vec<Option<T>> = (0..length).map(|_| None).collect();
+		for (node, value) in values.into_iter().enumerate() {
+			ordered[positions[node]] = Some(value);
+		}
+
+		let ordered: Vec<T> = ordered.into_iter().map(|value| value.unwrap()).collect();
+		let reversed = ordered.iter().rev().cloned().collect::<Vec<_>>().into_iter();
+		HeavyLightDecomposition {
+			tree: LazySegmentTree::construct_implicit(ordered.into_iter(), operator.clone(), sifter.clone()),
+			reversed: LazySegmentTree::construct_implicit(reversed, operator.clone(), sifter),
+			operator,
+			indexes,
+			nodes,
+			parents,
+			depths,
+			heads,
+			positions,
+			sizes,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// Finds the lowest common ancestor of two nodes
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn lca(&self, a: &N, b: &N) -> &N {
+		let (mut a, mut b) = (self.index(a), self.index(b));
+		while self.heads[a] != self.heads[b] {
+			if self.depths[self.heads[a]] < self.depths[self.heads[b]] { swap(&mut a, &mut b); }
+			a = self.parents[self.heads[a]].unwrap();
+		}
+
+		if self.depths[a] < self.depths[b] { &self.nodes[a] } else { &self.nodes[b] }
+	}
+
+	/// Combines the values of every node on the path between two nodes
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn query_path(&self, a: &N, b: &N) -> OwnedRef<'_, T> {
+		self.query_ranges(self.path_ranges(a, b, false))
+	}
+
+	/// Combines the values of every edge on the path between two nodes
+	///
+	/// The identity of the operator is returned if the nodes are equal.
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	/// The operator must have an identity if the nodes are equal
+	pub fn query_path_edges(&self, a: &N, b: &N) -> OwnedRef<'_, T> {
+		self.query_ranges(self.path_ranges(a, b, true))
+	}
+
+	/// Applies `delta` to the value of every node on the path between two nodes
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn update_path(&mut self, a: &N, b: &N, delta: &D) {
+		for (range, _) in self.path_ranges(a, b, false) {
+			self.update_range(range, delta);
+		}
+	}
+
+	/// Applies `delta` to the value of every edge on the path between two nodes
+	///
+	/// # Panics
+	///
+	/// Both nodes must be in the tree
+	pub fn update_path_edges(&mut self, a: &N, b: &N, delta: &D) {
+		for (range, _) in self.path_ranges(a, b, true) {
+			self.update_range(range, delta);
+		}
+	}
+
+	/// Combines the values of every node in the subtree of `node`
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn query_subtree(&self, node: &N) -> OwnedRef<'_, T> {
+		self.tree.query(self.subtree_range(node))
+	}
+
+	/// Applies `delta` to the value of every node in the subtree of `node`
+	///
+	/// # Panics
+	///
+	/// `node` must be in the tree
+	pub fn update_subtree(&mut self, node: &N, delta: &D) {
+		let range = self.subtree_range(node);
+		self.update_range(range, delta);
+	}
+
+	fn index(&self, node: &N) -> usize {
+		*self.indexes.get(node).expect("node is not in the tree")
+	}
+
+	fn subtree_range(&self, node: &N) -> Range<usize> {
+		let node = self.index(node);
+		self.positions[node]..self.positions[node] + self.sizes[node]
+	}
+
+	/// Applies `delta` to a range of positions in both orders
+	fn update_range(&mut self, range: Range<usize>, delta: &D) {
+		let length = self.nodes.len();
+		self.reversed.update_range(length - range.end..length - range.start, delta);
+		self.tree.update_range(range, delta);
+	}
+
+	/// Collects the ranges of positions that cover the path from `a` to `b` in order
+	///
+	/// Each range is paired with whether it is walked upwards, which is
+	/// against the order of the positions.
+	fn path_ranges(&self, a: &N, b: &N, edges: bool) -> Vec<(Range<usize>, bool)> {
+		let (mut a, mut b) = (self.index(a), self.index(b));
+		let (mut upwards, mut downwards) = (Vec::new(), Vec::new());
+		while self.heads[a] != self.heads[b] {
+			let (head_a, head_b) = (self.heads[a], self.heads[b]);
+			if self.depths[head_a] >= self.depths[head_b] {
+				upwards.push((self.positions[head_a]..self.positions[a] + 1, true));
+				a = self.parents[head_a].unwrap();
+			} else {
+				downwards.push((self.positions[head_b]..self.positions[b] + 1, false));
+				b = self.parents[head_b].unwrap();
+			}
+		}
+
+		let (top, bottom) = if self.depths[a] < self.depths[b] { (a, b) } else { (b, a) };
+		let start = self.positions[top] + edges as usize;
+		if start <= self.positions[bottom] {
+			upwards.push((start..self.positions[bottom] + 1, bottom == a));
+		}
+
+		// The ranges on the side of `b` were found from `b` towards the lowest common ancestor
+		upwards.extend(downwards.into_iter().rev());
+		upwards
+	}
+
+	fn query_ranges(&self, ranges: Vec<(Range<usize>, bool)>) -> OwnedRef<'_, T> {
+		let length = self.nodes.len();
+		let mut result: Option<OwnedRef<'_, T>> = None;
+		for (range, upwards) in ranges {
+			let value = match upwards {
+				true => self.reversed.query(length - range.end..length - range.start),
+				false => self.tree.query(range),
+			};
+			result = Some(match result {
+				Some(result) => self.operator.operate(&result, &value).into(),
+				None => value,
+			});
+		}
+
+		result.unwrap_or_else(|| {
+			self.operator.identity().expect("operator has no identity for an empty path").into()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_paths() {
+		use graph::HalfEdge;
+		use graph::rooted_tree::fixture;
+		use graph::WeightedEdge;
+
+		let parents = fixture::PARENTS;
+		let mut weights: Vec<u64> = (0..parents.len()).map(fixture::weight).collect();
+		weights[0] = 0;
+
+		let value = |_: &usize, edge: Option<&HalfEdge<usize, u64>>| edge.map_or(0, |edge| *edge.weight());
+		let sifter = |current: &u64, delta: &u64, _| *current + *delta;
+		let mut decomposition = HeavyLightDecomposition::construct(&fixture::graph(&parents), &0, value,
+		                                                           ::operator::maximum_bounded(), sifter);
+
+		// The weight of every edge is held by its lower node
+		let edges = |a: usize, b: usize| {
+			let lca = fixture::lca(&parents, a, b).unwrap();
+			fixture::path(&parents, a, b).unwrap().into_iter().filter(move |node| *node != lca)
+		};
+
+		for step in 0..4 {
+			for a in 0..parents.len() {
+				for b in 0..parents.len() {
+					let lca = fixture::lca(&parents, a, b).unwrap();
+					assert_eq!(decomposition.lca(&a, &b), &lca);
+
+					let expected = edges(a, b).map(|node| weights[node]).max().unwrap_or(0);
+					assert_eq!(*decomposition.query_path_edges(&a, &b), expected);
+					assert_eq!(*decomposition.query_path(&a, &b), expected.max(weights[lca]));
+				}
+
+				let subtree = (0..parents.len()).filter(|node| fixture::ancestors(&parents, *node).contains(&a));
+				assert_eq!(*decomposition.query_subtree(&a), subtree.map(|node| weights[node]).max().unwrap());
+			}
+
+			let (a, b) = (step * 3 + 2, 11 - step);
+			decomposition.update_path_edges(&a, &b, &(step as u64 + 2));
+			edges(a, b).for_each(|node| weights[node] += step as u64 + 2);
+		}
+
+		decomposition.update_subtree(&1, &10);
+		let expected = [6, 3, 1, 4, 11].iter().map(|node| weights[*node] + 10).max().unwrap();
+		assert_eq!(*decomposition.query_path(&6, &11), expected);
+	}
+
+	#[test]
+	fn test_path_order() {
+		use graph::HalfEdge;
+		use graph::rooted_tree::fixture;
+
+		// Digits are concatenated so the direction of every query is visible
+		let operator = |a: &(u64, u64), b: &(u64, u64)| (a.0 * b.1 + b.0, a.1 * b.1);
+		let digit = |node: usize| (node as u64 % 9 + 1, 10);
+		let value = |node: &usize, _: Option<&HalfEdge<usize, u64>>| digit(*node);
+		let sifter = |current: &(u64, u64), _: &(), _| *current;
+		let parents = fixture::PARENTS;
+		let decomposition = HeavyLightDecomposition::construct(&fixture::graph(&parents), &0, value, operator, sifter);
+
+		for a in 0..parents.len() {
+			for b in 0..parents.len() {
+				let path = fixture::path(&parents, a, b).unwrap();
+				let expected = path.into_iter().fold((0, 1), |aggregate, node| operator(&aggregate, &digit(node)));
+				assert_eq!(*decomposition.query_path(&a, &b), expected);
+			}
+		}
+	}
+}
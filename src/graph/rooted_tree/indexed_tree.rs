@@ -0,0 +1,54 @@
+use collections::BTreeMap;
+use graph::Edge;
+use graph::Graph;
+use Vec;
+
+/// The nodes of a rooted tree numbered in breadth first order
+///
+/// The root is numbered zero and every parent is numbered before its
+/// children. The rooted tree structures are built on top of these numbers.
+pub struct IndexedTree<N> {
+	pub indexes: BTreeMap<N, usize>,
+	pub nodes: Vec<N>,
+	pub parents: Vec<Option<usize>>,
+	pub depths: Vec<usize>,
+	pub children: Vec<Vec<usize>>,
+}
+
+impl<N> IndexedTree<N> where N: Ord + Clone {
+	/// Numbers the nodes of the tree reachable from `root`
+	///
+	/// `visit` is given the number of the parent and the edge to each child
+	/// in the order the children are numbered. Edges back to nodes that have
+	/// already been reached are ignored so undirected graphs can be used.
+	pub fn construct<'g, G, F>(graph: &'g G, root: &N, mut visit: F) -> IndexedTree<N>
+		where G: Graph<'g>, G::Edge: Edge<Node=N>, F: FnMut(usize, &G::Edge) {
+		let mut indexes = BTreeMap::new();
+		indexes.insert(root.clone(), 0);
+		let mut tree = IndexedTree {
+			indexes,
+			nodes: vec![root.clone()],
+			parents: vec![None],
+			depths: vec![0],
+			children: vec![Vec::new()],
+		};
+
+		let mut index = 0;
+		while index < tree.nodes.len() {
+			let node = tree.nodes[index].clone();
+			for edge in graph.neighbours(&node) {
+				if tree.indexes.contains_key(edge.end_node()) { continue; }
+				let child = tree.nodes.len();
+				tree.indexes.insert(edge.end_node().clone(), child);
+				tree.nodes.push(edge.end_node().clone());
+				tree.parents.push(Some(index));
+				tree.depths.push(tree.depths[index] + 1);
+				tree.children.push(Vec::new());
+				tree.children[index].push(child);
+				visit(index, edge);
+			}
+			index += 1;
+		}
+		tree
+	}
+}
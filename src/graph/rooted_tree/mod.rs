@@ -0,0 +1,14 @@
+pub use self::binary_lifting::BinaryLifting;
+pub use self::centroid_decomposition::CentroidDecomposition;
+pub use self::euler_tour::EulerTour;
+pub use self::euler_tour::LowestCommonAncestor;
+pub use self::heavy_light::HeavyLightDecomposition;
+
+mod indexed_tree;
+mod heavy_light;
+mod euler_tour;
+mod binary_lifting;
+mod centroid_decomposition;
+
+#[cfg(test)]
+mod fixture;
\ No newline at end of file
@@ -7,11 +7,21 @@ extern crate alloc as collections;
 #[cfg(feature = "std")]
 #[macro_use]
 extern crate std;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
+#[cfg(not(feature = "std"))]
+use collections::boxed::Box;
 #[cfg(not(feature = "std"))]
 use collections::Vec;
+pub use self::error::Error;
 pub use self::owned_ref::OwnedRef;
 #[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
 use std::collections as collections;
 #[cfg(feature = "std")]
 use std::vec::Vec;
@@ -23,6 +33,8 @@ pub mod graph;
 pub mod provider;
 pub mod math;
 mod owned_ref;
+mod error;
+mod range;
 
 pub trait FixedDataSource<T>: DoubleEndedIterator<Item=T> + ExactSizeIterator<Item=T> {}
 
@@ -1,15 +1,129 @@
+use provider::ClosureProvider;
+use provider::Provider;
+
 pub trait AssociativeOperator<T>: Fn(&T, &T) -> T {}
 
 impl<F, T> AssociativeOperator<T> for F where F: ?Sized + Fn(&T, &T) -> T {}
 
-pub fn summation<T>() -> impl AssociativeOperator<T> where T: ::core::ops::Add<Output=T> + Clone {
+/// An associative operator that may carry an identity element
+///
+/// The identity element `e` satisfies `operate(e, x) == x` and `operate(x, e) == x`.
+/// Every closure implementing `AssociativeOperator` is a `Monoid` without a
+/// known identity. An identity can be attached to a closure with `with_identity`.
+///
+/// Data structures use the identity to answer queries over empty ranges.
+pub trait Monoid<T> {
+	fn operate(&self, left: &T, right: &T) -> T;
+
+	/// Creates the identity element
+	///
+	/// Returns `None` if the identity is not known
+	fn identity(&self) -> Option<T>;
+}
+
+impl<F, T> Monoid<T> for F where F: AssociativeOperator<T> {
+	fn operate(&self, left: &T, right: &T) -> T {
+		self(left, right)
+	}
+
+	fn identity(&self) -> Option<T> {
+		None
+	}
+}
+
+#[derive(Clone)]
+pub struct IdentityOperator<O, P> {
+	operator: O,
+	identity: P,
+}
+
+impl<T, O, P> Monoid<T> for IdentityOperator<O, P> where O: AssociativeOperator<T>, P: Provider<T> {
+	fn operate(&self, left: &T, right: &T) -> T {
+		(self.operator)(left, right)
+	}
+
+	fn identity(&self) -> Option<T> {
+		Some(self.identity.create())
+	}
+}
+
+/// Attaches an identity element to an associative operator
+pub fn with_identity<T, O, P>(operator: O, identity: P) -> IdentityOperator<O, P>
+	where O: AssociativeOperator<T>, P: Provider<T> {
+	IdentityOperator {
+		operator,
+		identity,
+	}
+}
+
+/// Types with a smallest and largest value
+///
+/// These provide the identities of `maximum` and `minimum` respectively.
+pub trait Bounded {
+	fn min_value() -> Self;
+	fn max_value() -> Self;
+}
+
+macro_rules! define_bounded {
+    ($data_type: ident) => {
+		impl Bounded for $data_type {
+			fn min_value() -> Self {
+				$data_type::MIN
+			}
+
+			fn max_value() -> Self {
+				$data_type::MAX
+			}
+		}
+    };
+}
+
+define_bounded!(i8);
+define_bounded!(i16);
+define_bounded!(i32);
+define_bounded!(i64);
+define_bounded!(isize);
+
+define_bounded!(u8);
+define_bounded!(u16);
+define_bounded!(u32);
+define_bounded!(u64);
+define_bounded!(usize);
+
+pub fn summation<T>() -> impl AssociativeOperator<T> + Clone where T: ::core::ops::Add<Output=T> + Clone {
 	|a: &T, b: &T| a.clone() + b.clone()
 }
 
-pub fn minimum<T>() -> impl AssociativeOperator<T> where T: Ord + Clone {
+pub fn minimum<T>() -> impl AssociativeOperator<T> + Clone where T: Ord + Clone {
 	|a: &T, b: &T| a.clone().min(b.clone())
 }
 
-pub fn maximum<T>() -> impl AssociativeOperator<T> where T: Ord + Clone {
+pub fn maximum<T>() -> impl AssociativeOperator<T> + Clone where T: Ord + Clone {
 	|a: &T, b: &T| a.clone().max(b.clone())
 }
+
+/// `summation` with the default value as its identity
+pub fn summation_default<T>() -> impl Monoid<T> + Clone where T: ::core::ops::Add<Output=T> + Clone + Default {
+	with_identity(summation(), ClosureProvider::from(T::default))
+}
+
+/// `minimum` with the largest value as its identity
+pub fn minimum_bounded<T>() -> impl Monoid<T> + Clone where T: Ord + Clone + Bounded {
+	with_identity(minimum(), ClosureProvider::from(T::max_value))
+}
+
+/// `maximum` with the smallest value as its identity
+pub fn maximum_bounded<T>() -> impl Monoid<T> + Clone where T: Ord + Clone + Bounded {
+	with_identity(maximum(), ClosureProvider::from(T::min_value))
+}
+
+/// Represents a closure that undoes an associative operator
+///
+/// For every `a` and `b`, `inverse(&operator(&a, &b), &b)` must be equal to `a`.
+pub trait InverseOperator<T>: Fn(&T, &T) -> T {}
+
+impl<F, T> InverseOperator<T> for F where F: ?Sized + Fn(&T, &T) -> T {}
+
+pub fn subtraction<T>() -> impl InverseOperator<T> + Clone where T: ::core::ops::Sub<Output=T> + Clone {
+	|a: &T, b: &T| a.clone() - b.clone()
+}
@@ -54,6 +54,15 @@ impl<F, T> From<F> for ClosureProvider<F, T> where F: Sized + Fn() -> T {
 	}
 }
 
+impl<F, T> Clone for ClosureProvider<F, T> where F: Clone {
+	fn clone(&self) -> ClosureProvider<F, T> {
+		ClosureProvider {
+			closure: self.closure.clone(),
+			_type: Default::default(),
+		}
+	}
+}
+
 impl<F, T> Provider<T> for ClosureProvider<F, T> where F: Fn() -> T {
 	fn create(&self) -> T {
 		(self.closure)()
@@ -11,9 +11,16 @@ use Vec;
 ///
 /// Typical applications of the `DisjointSet` include the minimum spanning
 /// tree algorithm, *Kruskal's algorithm*.
+///
+/// The smaller group is always placed under the larger group so that
+/// finding the parent of a value takes amortized O(`α(n)`) time.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DisjointSet<T> where T: Ord {
 	indexes: BTreeMap<T, usize>,
 	parents: Vec<RefCell<usize>>,
+	/// The number of values in each group, only valid for parents
+	sizes: Vec<usize>,
+	component_count: usize,
 }
 
 impl<T> DisjointSet<T> where T: Ord {
@@ -21,13 +28,21 @@ impl<T> DisjointSet<T> where T: Ord {
 		DisjointSet {
 			indexes: BTreeMap::new(),
 			parents: Vec::new(),
+			sizes: Vec::new(),
+			component_count: 0,
 		}
 	}
 
+	/// Places a value in a group of its own
+	///
+	/// Nothing happens if the value is already in a group.
 	pub fn make_set(&mut self, node: T) {
+		if self.is_set(&node) { return; }
 		let index = self.parents.len();
 		self.indexes.insert(node, index);
 		self.parents.push(RefCell::new(index));
+		self.sizes.push(1);
+		self.component_count += 1;
 	}
 
 	pub fn is_set(&self, node: &T) -> bool {
@@ -52,17 +67,59 @@ impl<T> DisjointSet<T> where T: Ord {
 		node
 	}
 
-	pub fn union(&mut self, a: &T, b: &T) {
+	/// Groups two values together
+	///
+	/// Returns false if the values were already grouped together.
+	pub fn union(&mut self, a: &T, b: &T) -> bool {
 		assert!(self.is_set(a) && self.is_set(b));
 		let a_index = self.indexes[a];
 		let b_index = self.indexes[b];
-		self.union_indexes(a_index, b_index);
+		self.union_indexes(a_index, b_index)
 	}
 
-	pub fn union_indexes(&mut self, a: usize, b: usize) {
-		let a_parent = self.find_parent_index(a);
-		let b_parent = self.find_parent_index(b);
+	pub fn union_indexes(&mut self, a: usize, b: usize) -> bool {
+		let mut a_parent = self.find_parent_index(a);
+		let mut b_parent = self.find_parent_index(b);
+		if a_parent == b_parent { return false; }
+
+		if self.sizes[a_parent] > self.sizes[b_parent] {
+			::core::mem::swap(&mut a_parent, &mut b_parent);
+		}
 		self.parents[a_parent].replace(b_parent);
+		self.sizes[b_parent] += self.sizes[a_parent];
+		self.component_count -= 1;
+		true
+	}
+
+	/// Returns the number of values grouped with `node` including itself
+	pub fn size_of(&self, node: &T) -> usize {
+		self.sizes[self.find_parent(node)]
+	}
+
+	/// Returns the number of separate groups
+	pub fn component_count(&self) -> usize {
+		self.component_count
+	}
+
+	/// Iterates over every value grouped with `node` including itself
+	///
+	/// Every value in the set is checked so this takes O(`n`) time.
+	pub fn members<'a>(&'a self, node: &T) -> impl Iterator<Item=&'a T> + 'a {
+		let parent = self.find_parent(node);
+		self.indexes.iter()
+		    .filter(move |(_, index)| self.find_parent_index(**index) == parent)
+		    .map(|(node, _)| node)
+	}
+
+	/// Lists every group of values
+	///
+	/// The values in each group are in ascending order.
+	pub fn components(&self) -> Vec<Vec<&T>> {
+		let mut components: BTreeMap<usize, Vec<&T>> = BTreeMap::new();
+		for (node, index) in self.indexes.iter() {
+			components.entry(self.find_parent_index(*index)).or_default().push(node);
+		}
+		components.into_values().collect()
 	}
 }
 
@@ -82,6 +139,25 @@ mod tests {
 		assert!(!set.connected(&0, &3));
 	}
 
+	#[test]
+	fn test_components() {
+		let mut set = DisjointSet::new();
+		(0..8).for_each(|node| set.make_set(node));
+		assert_eq!(set.component_count(), 8);
+		assert!(set.union(&0, &1));
+		assert!(set.union(&2, &3));
+		assert!(set.union(&1, &3));
+		assert!(!set.union(&0, &2));
+		assert!(set.union(&5, &6));
+		set.make_set(5);
+
+		assert_eq!(set.size_of(&2), 4);
+		assert_eq!(set.size_of(&6), 2);
+		assert_eq!(set.component_count(), 4);
+		assert_eq!(set.members(&3).cloned().collect::<Vec<_>>(), [0, 1, 2, 3]);
+		assert_eq!(set.components(), [vec![&0, &1, &2, &3], vec![&4], vec![&5, &6], vec![&7]]);
+	}
+
 	#[test]
 	fn test_reference() {
 		let mut set = DisjointSet::new();
@@ -94,4 +170,16 @@ mod tests {
 		assert!(set.connected(&&0, &&2));
 		assert!(!set.connected(&&0, &&3));
 	}
-}
\ No newline at end of file
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_serde() {
+		let mut set = DisjointSet::new();
+		(0..4).for_each(|node| set.make_set(node));
+		set.union(&0, &2);
+		let serialized = ::serde_json::to_string(&set).unwrap();
+		let set: DisjointSet<i32> = ::serde_json::from_str(&serialized).unwrap();
+		assert!(set.connected(&0, &2));
+		assert!(!set.connected(&1, &3));
+	}
+}
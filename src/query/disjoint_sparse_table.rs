@@ -0,0 +1,164 @@
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use math::integer_log2;
+use operator::Monoid;
+use OwnedRef;
+use Vec;
+
+/// `DisjointSparseTable` answers range queries with any associative operator in constant time
+///
+/// Unlike `SparseTable` the two values that answer a query never overlap so
+/// operators that are not idempotent such as addition can be used. Each
+/// level splits the data into blocks of `2^level` values. Every value in the
+/// left half of a block stores the combination up to the middle of the block
+/// and every value in the right half stores the combination from the middle.
+/// A query is answered by the level where both ends of the range are in the
+/// same block but in different halves:
+/// - Construct: O(`n log n`)
+/// - Query: O(`1`) with at most one use of the operator
+pub struct DisjointSparseTable<T, O> {
+	table: Vec<Vec<T>>,
+	operator: O,
+}
+
+impl<T, O> DisjointSparseTable<T, O> where T: Clone, O: Monoid<T> {
+	pub fn compute<S>(data: S, operator: O) -> DisjointSparseTable<T, O> where S: FixedDataSource<T> {
+		let data: Vec<T> = data.collect();
+		let length = data.len();
+		let mut table = vec![data];
+
+		let mut level = 1;
+		while 1 << (level - 1) < length {
+			let half = 1 << (level - 1);
+			let mut values = table[0].clone();
+			for middle in (half..length).step_by(half << 1) {
+				for index in (middle - half..middle - 1).rev() {
+					values[index] = operator.operate(&values[index], &values[index + 1]);
+				}
+
+				let end = length.min(middle + half);
+				for index in middle + 1..end {
+					values[index] = operator.operate(&values[index - 1], &values[index]);
+				}
+			}
+
+			table.push(values);
+			level += 1;
+		}
+
+		DisjointSparseTable {
+			table,
+			operator,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.table[0].len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.table[0].is_empty()
+	}
+
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	/// The operator must have an identity if the range is empty
+	pub fn query(&self, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if it is empty and the operator has no identity
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		let range = ::range::bounds(range, self.len())?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		let (left, right) = (range.start, range.end - 1);
+		if left == right {
+			return Ok((&self.table[0][left]).into());
+		}
+
+		// The highest differing bit decides the block where the ends are in different halves
+		let level = integer_log2((left ^ right) as u64) as usize + 1;
+		let values = &self.table[level];
+		Ok(self.operator.operate(&values[left], &values[right]).into())
+	}
+}
+
+/// The operator is not serialized and must be given again with `deserialize`
+#[cfg(feature = "serde")]
+impl<T, O> ::serde::Serialize for DisjointSparseTable<T, O> where T: ::serde::Serialize {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: ::serde::Serializer {
+		self.table.serialize(serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<T, O> DisjointSparseTable<T, O> {
+	/// Deserializes a table with the operator it was computed with
+	pub fn deserialize<'de, D>(deserializer: D, operator: O) -> Result<DisjointSparseTable<T, O>, D::Error>
+		where D: ::serde::Deserializer<'de>, T: ::serde::Deserialize<'de> {
+		let table = ::serde::Deserialize::deserialize(deserializer)?;
+		Ok(DisjointSparseTable {
+			table,
+			operator,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test() {
+		use core::ops::Deref;
+
+		let data: Vec<i64> = (0..13).map(|index| (index * 7) % 11 - 4).collect();
+		let table = DisjointSparseTable::compute(data.clone().into_iter(), ::operator::summation_default());
+		for start in 0..=data.len() {
+			for end in start..=data.len() {
+				assert_eq!(table.query(start..end).deref(), &data[start..end].iter().sum::<i64>());
+			}
+		}
+		assert_eq!(table.try_query(3..14).err(), Some(Error::OutOfBounds { index: 13, length: 13 }));
+	}
+
+	#[test]
+	fn test_non_commutative() {
+		use core::ops::Deref;
+
+		type Matrix = [[i64; 2]; 2];
+		let operator = |a: &Matrix, b: &Matrix| {
+			let mut product = [[0; 2]; 2];
+			for (row, values) in product.iter_mut().enumerate() {
+				for (column, value) in values.iter_mut().enumerate() {
+					*value = a[row][0] * b[0][column] + a[row][1] * b[1][column];
+				}
+			}
+			product
+		};
+
+		let data: Vec<Matrix> = (0..9).map(|index| [[1, index], [index % 3, 1]]).collect();
+		let table = DisjointSparseTable::compute(data.clone().into_iter(), operator);
+		for start in 0..data.len() {
+			for end in start + 1..=data.len() {
+				let expected = data[start + 1..end].iter().fold(data[start], |product, value| operator(&product, value));
+				assert_eq!(table.query(start..end).deref(), &expected);
+			}
+		}
+		assert_eq!(table.try_query(2..2).err(), Some(Error::EmptyRange));
+	}
+}
@@ -1,3 +1,5 @@
+use core::ops::RangeBounds;
+use Error;
 use math::integer_log2;
 use Vec;
 
@@ -45,8 +47,24 @@ impl<'t, T> IndexSparseTable<'t, T> where T: Ord {
 		}
 	}
 
-	pub fn query_index(&self, left: usize, right: usize) -> usize {
-		assert!(left <= right && right < self.table[0].len());
+	/// Get the index of the maximum in a range such as `2..5` or `..`
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array and cannot be empty
+	pub fn query_index(&self, range: impl RangeBounds<usize>) -> usize {
+		self.try_query_index(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the index of the maximum in a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or is empty
+	pub fn try_query_index(&self, range: impl RangeBounds<usize>) -> Result<usize, Error> {
+		let range = ::range::bounds(range, self.table[0].len())?;
+		if range.is_empty() { return Err(Error::EmptyRange); }
+		let (left, right) = (range.start, range.end - 1);
 		let range_length = right - left;
 		let level = integer_log2(range_length as u64 + 1) as usize;
 		let right_range_index = (right + 1) - (1 << level);
@@ -55,14 +73,28 @@ impl<'t, T> IndexSparseTable<'t, T> where T: Ord {
 		let right_range_index = self.table[level][right_range_index];
 
 		if self.data[left_range_index] > self.data[right_range_index] {
-			left_range_index
+			Ok(left_range_index)
 		} else {
-			right_range_index
+			Ok(right_range_index)
 		}
 	}
 
-	pub fn query(&self, left: usize, right: usize) -> &T {
-		&self.data[self.query_index(left, right)]
+	/// Get the maximum in a range
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array and cannot be empty
+	pub fn query(&self, range: impl RangeBounds<usize>) -> &T {
+		&self.data[self.query_index(range)]
+	}
+
+	/// Get the maximum in a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or is empty
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<&T, Error> {
+		self.try_query_index(range).map(|index| &self.data[index])
 	}
 }
 
@@ -76,8 +108,18 @@ mod tests {
 		let table = IndexSparseTable::compute(data);
 		for right in 0..=6 {
 			for left in 0..=right {
-				assert_eq!(table.query(left, right), &(right + 1));
+				assert_eq!(table.query(left..=right), &(right + 1));
 			}
 		}
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn test_fallible() {
+		let data = &[3, 1, 4, 1, 5];
+		let table = IndexSparseTable::compute(data);
+		assert_eq!(table.query_index(..), 4);
+		assert_eq!(table.try_query(1..4), Ok(&4));
+		assert_eq!(table.try_query_index(2..2), Err(Error::EmptyRange));
+		assert_eq!(table.try_query(3..6), Err(Error::OutOfBounds { index: 5, length: 5 }));
+	}
+}
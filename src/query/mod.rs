@@ -1,7 +1,17 @@
 pub use self::disjoint_set::DisjointSet;
+pub use self::disjoint_sparse_table::DisjointSparseTable;
 pub use self::index_sparse_table::IndexSparseTable;
+pub use self::offline_connectivity::OfflineConnectivity;
+pub use self::rollback_disjoint_set::RollbackDisjointSet;
 pub use self::sparse_table::SparseTable;
+pub use self::wavelet_matrix::WaveletMatrix;
+pub use self::weighted_disjoint_set::WeightedDisjointSet;
 
 mod index_sparse_table;
 mod sparse_table;
-mod disjoint_set;
\ No newline at end of file
+mod disjoint_sparse_table;
+mod disjoint_set;
+mod rollback_disjoint_set;
+mod offline_connectivity;
+mod weighted_disjoint_set;
+mod wavelet_matrix;
\ No newline at end of file
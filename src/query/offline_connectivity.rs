@@ -0,0 +1,180 @@
+use collections::BTreeMap;
+use super::RollbackDisjointSet;
+use Vec;
+
+/// Answers connectivity queries over a graph whose edges are added and removed
+///
+/// Every operation is recorded first and all queries are answered together
+/// by `solve`. Each edge is present for a contiguous range of queries so the
+/// ranges are placed in a segment tree over the queries. A depth first walk
+/// of the segment tree adds the edges of each node to a `RollbackDisjointSet`
+/// and undoes them when leaving the node:
+/// - Add edge, remove edge and query: O(`log m`)
+/// - Solve: O(`m log m log n`)
+///
+/// where `m` is the number of operations and `n` is the number of vertices.
+pub struct OfflineConnectivity {
+	vertex_count: usize,
+	/// The queries made so far where each query is a pair of vertices
+	queries: Vec<(usize, usize)>,
+	/// The query index at which each present edge was added
+	present: BTreeMap<(usize, usize), Vec<usize>>,
+	/// Edges together with the range of queries they are present for
+	intervals: Vec<(usize, usize, (usize, usize))>,
+}
+
+impl OfflineConnectivity {
+	/// Creates a graph with the vertices `0..vertex_count` and no edges
+	pub fn new(vertex_count: usize) -> OfflineConnectivity {
+		OfflineConnectivity {
+			vertex_count,
+			queries: Vec::new(),
+			present: BTreeMap::new(),
+			intervals: Vec::new(),
+		}
+	}
+
+	/// Adds an undirected edge
+	///
+	/// An edge can be added more than once and is then present until it
+	/// is removed the same number of times.
+	///
+	/// # Panics
+	///
+	/// Both vertices must be less than the vertex count
+	pub fn add_edge(&mut self, a: usize, b: usize) {
+		assert!(a < self.vertex_count && b < self.vertex_count);
+		let time = self.queries.len();
+		self.present.entry(edge_key(a, b)).or_default().push(time);
+	}
+
+	/// Removes an undirected edge
+	///
+	/// Returns false if the edge is not present.
+	pub fn remove_edge(&mut self, a: usize, b: usize) -> bool {
+		let key = edge_key(a, b);
+		let start = match self.present.get_mut(&key).and_then(|starts| starts.pop()) {
+			Some(start) => start,
+			None => return false,
+		};
+
+		if self.present[&key].is_empty() { self.present.remove(&key); }
+		self.intervals.push((start, self.queries.len(), key));
+		true
+	}
+
+	/// Asks whether two vertices are connected by the edges present now
+	///
+	/// Returns the index of the answer in the result of `solve`.
+	///
+	/// # Panics
+	///
+	/// Both vertices must be less than the vertex count
+	pub fn query(&mut self, a: usize, b: usize) -> usize {
+		assert!(a < self.vertex_count && b < self.vertex_count);
+		self.queries.push((a, b));
+		self.queries.len() - 1
+	}
+
+	/// Answers every query in the order they were made
+	pub fn solve(self) -> Vec<bool> {
+		let length = self.queries.len();
+		let mut answers = vec![false; length];
+		if length == 0 { return answers; }
+
+		let mut nodes: Vec<Vec<(usize, usize)>> = vec![Vec::new(); 4 * length];
+		let remaining = self.present.into_iter()
+		    .flat_map(|(key, starts)| starts.into_iter().map(move |start| (start, length, key)));
+		for (start, end, key) in self.intervals.into_iter().chain(remaining) {
+			if start < end {
+				insert_interval(&mut nodes, 0, 0, length - 1, start, end - 1, key);
+			}
+		}
+
+		let mut set = RollbackDisjointSet::new(self.vertex_count);
+		solve_recursively(&nodes, &self.queries, &mut set, &mut answers, 0, 0, length - 1);
+		answers
+	}
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+	if a <= b { (a, b) } else { (b, a) }
+}
+
+fn insert_interval(nodes: &mut [Vec<(usize, usize)>], node: usize, current_left: usize, current_right: usize,
+                   left: usize, right: usize, edge: (usize, usize)) {
+	if right < current_left || current_right < left { return; }
+	if left <= current_left && current_right <= right {
+		nodes[node].push(edge);
+		return;
+	}
+
+	let (middle_left, middle_right) = ::tree::functions::split_range(current_left, current_right);
+	insert_interval(nodes, 2 * node + 1, current_left, middle_left, left, right, edge);
+	insert_interval(nodes, 2 * node + 2, middle_right, current_right, left, right, edge);
+}
+
+fn solve_recursively(nodes: &[Vec<(usize, usize)>], queries: &[(usize, usize)], set: &mut RollbackDisjointSet,
+                     answers: &mut [bool], node: usize, current_left: usize, current_right: usize) {
+	let snapshot = set.snapshot();
+	for &(a, b) in nodes[node].iter() {
+		set.union(a, b);
+	}
+
+	if current_left == current_right {
+		let (a, b) = queries[current_left];
+		answers[current_left] = set.connected(a, b);
+	} else {
+		let (middle_left, middle_right) = ::tree::functions::split_range(current_left, current_right);
+		solve_recursively(nodes, queries, set, answers, 2 * node + 1, current_left, middle_left);
+		solve_recursively(nodes, queries, set, answers, 2 * node + 2, middle_right, current_right);
+	}
+	set.rollback(snapshot);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_connectivity() {
+		let vertex_count = 7;
+		let mut connectivity = OfflineConnectivity::new(vertex_count);
+		let mut edges: Vec<(usize, usize)> = Vec::new();
+		let mut expected = Vec::new();
+
+		for step in 0..120usize {
+			let (a, b) = ((step * 3) % vertex_count, (step * 5 + step / 7) % vertex_count);
+			match step % 4 {
+				0 | 1 => {
+					connectivity.add_edge(a, b);
+					edges.push((a, b));
+				}
+				2 => {
+					let position = edges.iter().position(|&edge| edge == (a, b) || edge == (b, a));
+					assert_eq!(connectivity.remove_edge(a, b), position.is_some());
+					position.map(|position| edges.remove(position));
+				}
+				_ => {
+					// Search the edges present now to find the expected answer
+					let mut reached = vec![a];
+					let mut index = 0;
+					while index < reached.len() {
+						let node = reached[index];
+						for &(x, y) in edges.iter() {
+							let next = if x == node { y } else if y == node { x } else { continue };
+							if !reached.contains(&next) { reached.push(next); }
+						}
+						index += 1;
+					}
+
+					assert_eq!(connectivity.query(a, b), expected.len());
+					expected.push(reached.contains(&b));
+				}
+			}
+		}
+
+		assert!(expected.iter().any(|answer| *answer) && expected.iter().any(|answer| !*answer));
+		assert_eq!(connectivity.solve(), expected);
+	}
+}
@@ -0,0 +1,125 @@
+use Vec;
+
+/// A variant of `DisjointSet` where unions can be undone
+///
+/// Values are identified by the indexes `0..length`. Parents are never
+/// compressed so that every union only changes a single parent and can be
+/// undone in O(`1`). The smaller group is always placed under the larger
+/// group so finding the parent of a value takes O(`log n`):
+/// - Construct: O(`n`)
+/// - Union and connected: O(`log n`)
+/// - Rollback: O(`1`) for every union that is undone
+pub struct RollbackDisjointSet {
+	parents: Vec<usize>,
+	/// The number of values in each group, only valid for parents
+	sizes: Vec<usize>,
+	/// The values that were placed under another value by each union
+	history: Vec<usize>,
+	component_count: usize,
+}
+
+impl RollbackDisjointSet {
+	/// Creates a set where each of the `length` values is in a group of its own
+	pub fn new(length: usize) -> RollbackDisjointSet {
+		RollbackDisjointSet {
+			parents: (0..length).collect(),
+			sizes: vec![1; length],
+			history: Vec::new(),
+			component_count: length,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.parents.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.parents.is_empty()
+	}
+
+	pub fn find_parent(&self, mut node: usize) -> usize {
+		while self.parents[node] != node {
+			node = self.parents[node];
+		}
+		node
+	}
+
+	pub fn connected(&self, a: usize, b: usize) -> bool {
+		self.find_parent(a) == self.find_parent(b)
+	}
+
+	/// Groups two values together
+	///
+	/// Returns false if the values were already grouped together. Nothing
+	/// is recorded in that case so there is nothing to undo.
+	pub fn union(&mut self, a: usize, b: usize) -> bool {
+		let mut a_parent = self.find_parent(a);
+		let mut b_parent = self.find_parent(b);
+		if a_parent == b_parent { return false; }
+
+		if self.sizes[a_parent] > self.sizes[b_parent] {
+			::core::mem::swap(&mut a_parent, &mut b_parent);
+		}
+		self.parents[a_parent] = b_parent;
+		self.sizes[b_parent] += self.sizes[a_parent];
+		self.history.push(a_parent);
+		self.component_count -= 1;
+		true
+	}
+
+	/// Returns the number of values grouped with `node` including itself
+	pub fn size_of(&self, node: usize) -> usize {
+		self.sizes[self.find_parent(node)]
+	}
+
+	/// Returns the number of separate groups
+	pub fn component_count(&self) -> usize {
+		self.component_count
+	}
+
+	/// Records the current state so that it can be restored with `rollback`
+	pub fn snapshot(&self) -> usize {
+		self.history.len()
+	}
+
+	/// Undoes every union made since `snapshot` was taken
+	///
+	/// # Panics
+	///
+	/// `snapshot` cannot be newer than the current state
+	pub fn rollback(&mut self, snapshot: usize) {
+		assert!(snapshot <= self.history.len());
+		while self.history.len() > snapshot {
+			let node = self.history.pop().unwrap();
+			let parent = self.parents[node];
+			self.sizes[parent] -= self.sizes[node];
+			self.parents[node] = node;
+			self.component_count += 1;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rollback() {
+		let mut set = RollbackDisjointSet::new(6);
+		assert!(set.union(0, 1));
+		let snapshot = set.snapshot();
+		assert!(set.union(1, 2));
+		assert!(set.union(3, 4));
+		assert!(!set.union(0, 2));
+		assert!(set.connected(0, 2));
+		assert_eq!(set.size_of(2), 3);
+		assert_eq!(set.component_count(), 3);
+
+		set.rollback(snapshot);
+		assert!(set.connected(0, 1));
+		assert!(!set.connected(0, 2));
+		assert!(!set.connected(3, 4));
+		assert_eq!(set.size_of(0), 2);
+		assert_eq!(set.component_count(), 5);
+	}
+}
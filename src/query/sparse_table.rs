@@ -1,6 +1,8 @@
+use core::ops::RangeBounds;
+use Error;
 use FixedDataSource;
 use math::integer_log2;
-use operator::AssociativeOperator;
+use operator::Monoid;
 use OwnedRef;
 use Vec;
 
@@ -14,14 +16,15 @@ pub struct SparseTable<T, O> {
 	operator: O,
 }
 
-impl<T, O> SparseTable<T, O> where O: AssociativeOperator<T> {
+impl<T, O> SparseTable<T, O> where O: Monoid<T> {
 	pub fn compute<S>(data: S, operator: O) -> SparseTable<T, O> where S: FixedDataSource<T> {
 		let mut table = Vec::new();
 		let data: Vec<T> = data.collect();
 		let length = data.len();
 		table.push(data);
 
-		for level in 1..=integer_log2(length as u64) as _ {
+		let levels = if length > 0 { integer_log2(length as u64) as usize } else { 0 };
+		for level in 1..=levels {
 			table.push(Vec::new());
 			for left_interval in 0..=(length - (1 << level)) {
 				let previous_level = level - 1;
@@ -31,7 +34,7 @@ impl<T, O> SparseTable<T, O> where O: AssociativeOperator<T> {
 				let value = {
 					let left_range = &table[level - 1][left_interval];
 					let right_range = &table[level - 1][right_interval];
-					operator(left_range, right_range)
+					operator.operate(left_range, right_range)
 				};
 
 				table[level].push(value);
@@ -44,16 +47,39 @@ impl<T, O> SparseTable<T, O> where O: AssociativeOperator<T> {
 		}
 	}
 
-	pub fn query(&self, mut left: usize, right: usize) -> OwnedRef<T> {
-		assert!(left <= right && right < self.table[0].len());
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	/// The operator must have an identity if the range is empty
+	pub fn query(&self, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if it is empty and the operator has no identity
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		let range = ::range::bounds(range, self.table[0].len())?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		let (mut left, right) = (range.start, range.end - 1);
 		let mut value: Option<OwnedRef<T>> = None;
-		let max_level = integer_log2(left as u64 + 1) as usize;
+		let max_level = integer_log2((right - left + 1) as u64) as usize;
 		for level in (0..=max_level).rev() {
 			let interval_length = 1 << level;
 			if left + interval_length - 1 <= right {
 				let table_value = &self.table[level][left];
 				let new_value: OwnedRef<T> = if let Some(value) = value {
-					(self.operator)(&value, &table_value).into()
+					self.operator.operate(&value, table_value).into()
 				} else {
 					table_value.into()
 				};
@@ -62,7 +88,28 @@ impl<T, O> SparseTable<T, O> where O: AssociativeOperator<T> {
 				left += interval_length;
 			}
 		}
-		value.unwrap()
+		Ok(value.unwrap())
+	}
+}
+
+/// The operator is not serialized and must be given again with `deserialize`
+#[cfg(feature = "serde")]
+impl<T, O> ::serde::Serialize for SparseTable<T, O> where T: ::serde::Serialize {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: ::serde::Serializer {
+		self.table.serialize(serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<T, O> SparseTable<T, O> {
+	/// Deserializes a table with the operator it was computed with
+	pub fn deserialize<'de, D>(deserializer: D, operator: O) -> Result<SparseTable<T, O>, D::Error>
+		where D: ::serde::Deserializer<'de>, T: ::serde::Deserialize<'de> {
+		let table = ::serde::Deserialize::deserialize(deserializer)?;
+		Ok(SparseTable {
+			table,
+			operator,
+		})
 	}
 }
 
@@ -78,10 +125,37 @@ mod tests {
 		let data = vec![6, 12, 24, 36, 36];
 		let operator = |a: &u64, b: &u64| greatest_common_divisor(*a, *b);
 		let table = SparseTable::compute(data.into_iter(), operator);
-		assert_eq!(table.query(0, 1).deref(), &6);
-		assert_eq!(table.query(0, 4).deref(), &6);
-		assert_eq!(table.query(3, 4).deref(), &36);
-		assert_eq!(table.query(2, 4).deref(), &12);
-		assert_eq!(table.query(1, 3).deref(), &12);
+		assert_eq!(table.query(0..=1).deref(), &6);
+		assert_eq!(table.query(0..=4).deref(), &6);
+		assert_eq!(table.query(3..=4).deref(), &36);
+		assert_eq!(table.query(2..=4).deref(), &12);
+		assert_eq!(table.query(1..=3).deref(), &12);
+
+		// Operators without an identity work with any ordered type
+		let table = SparseTable::compute(vec!['s', 't', 'r', 'u', 't'].into_iter(), ::operator::minimum());
+		assert_eq!(table.query(1..).deref(), &'r');
+	}
+
+	#[test]
+	fn test_empty() {
+		use core::ops::Deref;
+		let table = SparseTable::compute(::core::iter::empty::<i32>(), ::operator::maximum_bounded());
+		assert_eq!(table.query(..).deref(), &i32::MIN);
+
+		let table = SparseTable::compute(vec![4, 1, 3].into_iter(), ::operator::summation_default());
+		assert_eq!(table.query(2..2).deref(), &0);
+		assert_eq!(table.query(0..=2).deref(), &8);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_serde() {
+		use core::ops::Deref;
+		let table = SparseTable::compute(vec![4, 1, 3, 6].into_iter(), ::operator::maximum());
+		let serialized = ::serde_json::to_string(&table).unwrap();
+		let mut deserializer = ::serde_json::Deserializer::from_str(&serialized);
+		let table: SparseTable<i32, _> = SparseTable::deserialize(&mut deserializer, ::operator::maximum()).unwrap();
+		assert_eq!(table.query(..3).deref(), &4);
+		assert_eq!(table.query(1..).deref(), &6);
 	}
 }
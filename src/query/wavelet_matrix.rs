@@ -0,0 +1,296 @@
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use math::integer_log2;
+use Vec;
+
+/// One bit of every value in the matrix
+struct BitLevel {
+	/// Number of zero bits before each position
+	zeros: Vec<usize>,
+	zero_count: usize,
+}
+
+impl BitLevel {
+	fn ones(&self, position: usize) -> usize {
+		position - self.zeros[position]
+	}
+}
+
+/// `WaveletMatrix` allows order statistic queries against immutable data
+///
+/// Values are compressed to their rank among the distinct values and the
+/// bits of every rank are stored level by level from the most significant
+/// bit. Each level is stably partitioned so that zero bits come first.
+/// Queries descend one level per bit of the number of distinct values `σ`:
+/// - Construct: O(`n log n`)
+/// - Rank, quantile and range counts: O(`log σ`)
+/// - Select: O(`log n log σ`)
+///
+/// Unlike `SparseTable` and `IndexSparseTable` this can answer questions
+/// such as the `k`-th smallest value in a range or how many values in a
+/// range lie between two bounds.
+pub struct WaveletMatrix<T> {
+	values: Vec<T>,
+	levels: Vec<BitLevel>,
+	length: usize,
+}
+
+impl<T> WaveletMatrix<T> where T: Ord {
+	pub fn compute(data: impl FixedDataSource<T>) -> WaveletMatrix<T> {
+		let mut data: Vec<(usize, T)> = data.enumerate().collect();
+		data.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+		let length = data.len();
+		let mut values: Vec<T> = Vec::new();
+		let mut codes = vec![0; length];
+		for (index, value) in data {
+			if values.last() != Some(&value) {
+				values.push(value);
+			}
+			codes[index] = values.len() - 1;
+		}
+
+		let bits = match values.len() {
+			0 | 1 => 0,
+			distinct => integer_log2(distinct as u64 - 1) + 1,
+		};
+
+		let mut levels = Vec::new();
+		for bit in (0..bits).rev() {
+			let mut zeros = Vec::with_capacity(length + 1);
+			zeros.push(0);
+			for code in codes.iter() {
+				let previous = *zeros.last().unwrap();
+				zeros.push(previous + ((code >> bit) & 1 ^ 1));
+			}
+
+			let zero_count = zeros[length];
+			let (mut lower, upper): (Vec<usize>, Vec<usize>) = codes.iter().partition(|code| (*code >> bit) & 1 == 0);
+			lower.extend(upper);
+			codes = lower;
+			levels.push(BitLevel { zeros, zero_count });
+		}
+
+		WaveletMatrix {
+			values,
+			levels,
+			length,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.length
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.length == 0
+	}
+
+	/// Counts the occurrences of `value` before `position`
+	///
+	/// # Panics
+	///
+	/// `position` cannot be greater than the length of the input array
+	pub fn rank(&self, value: &T, position: usize) -> usize {
+		assert!(position <= self.length);
+		let code = match self.values.binary_search(value) {
+			Ok(code) => code,
+			Err(_) => return 0,
+		};
+
+		let (mut left, mut right) = (0, position);
+		for (level, bits) in self.levels.iter().zip(self.bit_shifts()) {
+			if (code >> bits) & 1 == 0 {
+				left = level.zeros[left];
+				right = level.zeros[right];
+			} else {
+				left = level.zero_count + level.ones(left);
+				right = level.zero_count + level.ones(right);
+			}
+		}
+		right - left
+	}
+
+	/// Finds the position of the `k`-th occurrence of `value` counting from zero
+	///
+	/// # Errors
+	///
+	/// Returns `None` if `value` occurs `k` times or fewer
+	pub fn select(&self, value: &T, k: usize) -> Option<usize> {
+		if self.rank(value, self.length) <= k { return None; }
+		let (mut lower, mut upper) = (0, self.length);
+		while lower < upper {
+			let middle = (lower + upper) / 2;
+			if self.rank(value, middle + 1) > k {
+				upper = middle;
+			} else {
+				lower = middle + 1;
+			}
+		}
+		Some(lower)
+	}
+
+	/// Finds the `k`-th smallest value in a range such as `2..5` or `..` counting from zero
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	/// `k` must be less than the length of the range
+	pub fn quantile(&self, range: impl RangeBounds<usize>, k: usize) -> &T {
+		self.try_quantile(range, k).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Finds the `k`-th smallest value in a range counting from zero
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if `k` is not less than the length of the range
+	pub fn try_quantile(&self, range: impl RangeBounds<usize>, mut k: usize) -> Result<&T, Error> {
+		let range = ::range::bounds(range, self.length)?;
+		if k >= range.len() { return Err(Error::OutOfBounds { index: k, length: range.len() }); }
+		let (mut left, mut right) = (range.start, range.end);
+		let mut code = 0;
+		for (level, bits) in self.levels.iter().zip(self.bit_shifts()) {
+			let (left_zeros, right_zeros) = (level.zeros[left], level.zeros[right]);
+			let zeros = right_zeros - left_zeros;
+			if k < zeros {
+				left = left_zeros;
+				right = right_zeros;
+			} else {
+				k -= zeros;
+				code |= 1 << bits;
+				left = level.zero_count + (left - left_zeros);
+				right = level.zero_count + (right - right_zeros);
+			}
+		}
+		Ok(&self.values[code])
+	}
+
+	/// Counts the values in a range that are less than or equal to `value`
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn count_less_equal(&self, range: impl RangeBounds<usize>, value: &T) -> usize {
+		self.try_count_less_equal(range, value).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Counts the values in a range that are less than or equal to `value`
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_count_less_equal(&self, range: impl RangeBounds<usize>, value: &T) -> Result<usize, Error> {
+		let range = ::range::bounds(range, self.length)?;
+		let bound = match self.values.binary_search(value) {
+			Ok(code) => code + 1,
+			Err(code) => code,
+		};
+		Ok(self.count_less_code(range.start, range.end, bound))
+	}
+
+	/// Counts the values in a range that lie within `lower..=upper`
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn range_frequency(&self, range: impl RangeBounds<usize>, lower: &T, upper: &T) -> usize {
+		self.try_range_frequency(range, lower, upper).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Counts the values in a range that lie within `lower..=upper`
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_range_frequency(&self, range: impl RangeBounds<usize>, lower: &T, upper: &T) -> Result<usize, Error> {
+		let range = ::range::bounds(range, self.length)?;
+		if lower > upper { return Ok(0); }
+		let lower = match self.values.binary_search(lower) {
+			Ok(code) | Err(code) => code,
+		};
+		let upper = match self.values.binary_search(upper) {
+			Ok(code) => code + 1,
+			Err(code) => code,
+		};
+		Ok(self.count_less_code(range.start, range.end, upper) - self.count_less_code(range.start, range.end, lower))
+	}
+
+	/// Counts the compressed values less than `bound` within `left..right`
+	fn count_less_code(&self, mut left: usize, mut right: usize, bound: usize) -> usize {
+		if bound >= self.values.len() { return right - left; }
+		let mut count = 0;
+		for (level, bits) in self.levels.iter().zip(self.bit_shifts()) {
+			let (left_zeros, right_zeros) = (level.zeros[left], level.zeros[right]);
+			if (bound >> bits) & 1 == 1 {
+				count += right_zeros - left_zeros;
+				left = level.zero_count + (left - left_zeros);
+				right = level.zero_count + (right - right_zeros);
+			} else {
+				left = left_zeros;
+				right = right_zeros;
+			}
+		}
+		count
+	}
+
+	/// Iterates over the bit shifts of each level from the most significant bit
+	fn bit_shifts(&self) -> impl Iterator<Item=usize> {
+		(0..self.levels.len()).rev()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test() {
+		let data = vec![5, 1, 9, 3, 7, 3, 8, 5, 4, 10, 0, 3];
+		let matrix = WaveletMatrix::compute(data.clone().into_iter());
+		assert_eq!(matrix.len(), data.len());
+
+		for right in 0..data.len() {
+			for left in 0..=right {
+				let mut sorted = data[left..=right].to_vec();
+				sorted.sort();
+				for (k, value) in sorted.iter().enumerate() {
+					assert_eq!(matrix.quantile(left..=right, k), value);
+				}
+
+				for value in -1..12 {
+					let expected = sorted.iter().filter(|element| **element <= value).count();
+					assert_eq!(matrix.count_less_equal(left..=right, &value), expected);
+					let expected = sorted.iter().filter(|element| (3..=value).contains(*element)).count();
+					assert_eq!(matrix.range_frequency(left..=right, &3, &value), expected);
+				}
+			}
+		}
+
+		for value in -1..12 {
+			for position in 0..=data.len() {
+				let expected = data[..position].iter().filter(|element| **element == value).count();
+				assert_eq!(matrix.rank(&value, position), expected);
+			}
+
+			let positions: Vec<usize> = (0..data.len()).filter(|index| data[*index] == value).collect();
+			for k in 0..=positions.len() {
+				assert_eq!(matrix.select(&value, k), positions.get(k).cloned());
+			}
+		}
+	}
+
+	#[test]
+	fn test_fallible() {
+		let matrix = WaveletMatrix::compute(vec![4, 2, 7, 2].into_iter());
+		assert_eq!(matrix.try_quantile(1.., 2), Ok(&7));
+		assert_eq!(matrix.try_quantile(1..1, 0), Err(Error::OutOfBounds { index: 0, length: 0 }));
+		assert_eq!(matrix.try_count_less_equal(.., &4), Ok(3));
+		assert_eq!(matrix.try_count_less_equal(2..2, &4), Ok(0));
+		assert_eq!(matrix.try_range_frequency(..=4, &2, &4).err(), Some(Error::OutOfBounds { index: 4, length: 4 }));
+		let (start, end) = (3, 1);
+		assert_eq!(matrix.try_range_frequency(start..end, &2, &4).err(), Some(Error::InvalidRange { start, end }));
+	}
+}
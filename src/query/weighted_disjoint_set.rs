@@ -0,0 +1,209 @@
+use collections::BTreeMap;
+use core::cell::RefCell;
+use Error;
+use operator::InverseOperator;
+use operator::Monoid;
+use Vec;
+
+/// A variant of `DisjointSet` that keeps the difference between grouped values
+///
+/// Values are grouped by constraints of the form `value(a) - value(b) = w`.
+/// The difference between any two grouped values is implied by the
+/// constraints and a constraint that contradicts them is rejected. The
+/// operator and its inverse take the place of addition and subtraction.
+/// Together they must form a commutative group so the operator must have an
+/// identity and be commutative:
+/// - Union: amortized O(`α(n)`)
+/// - Difference: amortized O(`α(n)`)
+///
+/// Each value stores its difference to its parent. The differences are
+/// combined when the parents are compressed.
+pub struct WeightedDisjointSet<T, W, O, I> where T: Ord {
+	indexes: BTreeMap<T, usize>,
+	parents: Vec<RefCell<usize>>,
+	/// The difference between each value and its parent
+	offsets: Vec<RefCell<W>>,
+	/// The number of values in each group, only valid for parents
+	sizes: Vec<usize>,
+	component_count: usize,
+	operator: O,
+	inverse: I,
+}
+
+impl<T, W, O, I> WeightedDisjointSet<T, W, O, I>
+	where T: Ord, W: Clone + PartialEq, O: Monoid<W>, I: InverseOperator<W> {
+	pub fn new(operator: O, inverse: I) -> WeightedDisjointSet<T, W, O, I> {
+		WeightedDisjointSet {
+			indexes: BTreeMap::new(),
+			parents: Vec::new(),
+			offsets: Vec::new(),
+			sizes: Vec::new(),
+			component_count: 0,
+			operator,
+			inverse,
+		}
+	}
+
+	/// Places a value in a group of its own
+	///
+	/// Nothing happens if the value is already in a group.
+	///
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn make_set(&mut self, node: T) {
+		if self.is_set(&node) { return; }
+		let index = self.parents.len();
+		self.indexes.insert(node, index);
+		self.parents.push(RefCell::new(index));
+		self.offsets.push(RefCell::new(self.identity()));
+		self.sizes.push(1);
+		self.component_count += 1;
+	}
+
+	pub fn is_set(&self, node: &T) -> bool {
+		self.indexes.contains_key(node)
+	}
+
+	pub fn connected(&self, a: &T, b: &T) -> bool {
+		self.find_parent(a) == self.find_parent(b)
+	}
+
+	pub fn find_parent(&self, node: &T) -> usize {
+		assert!(self.is_set(node));
+		self.find_parent_index(self.indexes[node]).0
+	}
+
+	/// Finds the parent of a value and the difference between the value and its parent
+	fn find_parent_index(&self, node: usize) -> (usize, W) {
+		let mut path = Vec::new();
+		let mut parent = node;
+		while *self.parents[parent].borrow() != parent {
+			path.push(parent);
+			parent = *self.parents[parent].borrow();
+		}
+
+		// The values closest to the parent are compressed first
+		for &current in path.iter().rev() {
+			let previous = *self.parents[current].borrow();
+			if previous == parent { continue; }
+			let offset = self.operator.operate(&self.offsets[current].borrow(), &self.offsets[previous].borrow());
+			self.offsets[current].replace(offset);
+			self.parents[current].replace(parent);
+		}
+
+		match node == parent {
+			true => (parent, self.identity()),
+			false => (parent, self.offsets[node].borrow().clone()),
+		}
+	}
+
+	/// Groups two values together with the constraint `value(a) - value(b) = weight`
+	///
+	/// Returns false if the values were already grouped together and the
+	/// constraint is already implied.
+	///
+	/// # Errors
+	///
+	/// `Conflict` if the values are grouped together with a different difference
+	///
+	/// # Panics
+	///
+	/// Both values must be in the set
+	pub fn union_with(&mut self, a: &T, b: &T, weight: W) -> Result<bool, Error> {
+		assert!(self.is_set(a) && self.is_set(b));
+		let (a_parent, a_offset) = self.find_parent_index(self.indexes[a]);
+		let (b_parent, b_offset) = self.find_parent_index(self.indexes[b]);
+		if a_parent == b_parent {
+			return match (self.inverse)(&a_offset, &b_offset) == weight {
+				true => Ok(false),
+				false => Err(Error::Conflict),
+			};
+		}
+
+		// The difference between the parent of `a` and the parent of `b`
+		let offset = (self.inverse)(&self.operator.operate(&weight, &b_offset), &a_offset);
+		let (child, parent, offset) = match self.sizes[a_parent] <= self.sizes[b_parent] {
+			true => (a_parent, b_parent, offset),
+			false => (b_parent, a_parent, (self.inverse)(&self.identity(), &offset)),
+		};
+
+		self.parents[child].replace(parent);
+		self.offsets[child].replace(offset);
+		self.sizes[parent] += self.sizes[child];
+		self.component_count -= 1;
+		Ok(true)
+	}
+
+	/// Returns `value(a) - value(b)` if the values are grouped together
+	///
+	/// # Panics
+	///
+	/// Both values must be in the set
+	pub fn difference(&self, a: &T, b: &T) -> Option<W> {
+		assert!(self.is_set(a) && self.is_set(b));
+		let (a_parent, a_offset) = self.find_parent_index(self.indexes[a]);
+		let (b_parent, b_offset) = self.find_parent_index(self.indexes[b]);
+		match a_parent == b_parent {
+			true => Some((self.inverse)(&a_offset, &b_offset)),
+			false => None,
+		}
+	}
+
+	/// Returns the number of values grouped with `node` including itself
+	pub fn size_of(&self, node: &T) -> usize {
+		self.sizes[self.find_parent(node)]
+	}
+
+	/// Returns the number of separate groups
+	pub fn component_count(&self) -> usize {
+		self.component_count
+	}
+
+	fn identity(&self) -> W {
+		self.operator.identity().expect("operator has no identity for a weighted disjoint set")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_ledger() {
+		use operator::subtraction;
+		use operator::summation_default;
+
+		let mut set = WeightedDisjointSet::new(summation_default(), subtraction());
+		(0..6).for_each(|node| set.make_set(node));
+		assert_eq!(set.union_with(&0, &1, 5), Ok(true));
+		assert_eq!(set.union_with(&2, &1, -3), Ok(true));
+		assert_eq!(set.union_with(&3, &4, 10), Ok(true));
+		assert_eq!(set.union_with(&4, &2, 1), Ok(true));
+		assert_eq!(set.difference(&0, &2), Some(8));
+		assert_eq!(set.difference(&3, &0), Some(3));
+		assert_eq!(set.difference(&1, &5), None);
+
+		assert_eq!(set.union_with(&0, &3, -3), Ok(false));
+		assert_eq!(set.union_with(&0, &3, 3), Err(Error::Conflict));
+		assert_eq!(set.difference(&0, &3), Some(-3));
+		assert_eq!(set.size_of(&4), 5);
+		assert_eq!(set.component_count(), 2);
+	}
+
+	#[test]
+	fn test_parity() {
+		use operator::with_identity;
+		use provider::ClosureProvider;
+
+		// Exclusive or is its own inverse
+		let xor = |a: &bool, b: &bool| a ^ b;
+		let mut set = WeightedDisjointSet::new(with_identity(xor, ClosureProvider::from(|| false)), xor);
+		(0..4).for_each(|node| set.make_set(node));
+		assert_eq!(set.union_with(&0, &1, true), Ok(true));
+		assert_eq!(set.union_with(&1, &2, true), Ok(true));
+		assert_eq!(set.union_with(&2, &3, false), Ok(true));
+		assert_eq!(set.difference(&0, &3), Some(false));
+		assert_eq!(set.union_with(&3, &0, true), Err(Error::Conflict));
+	}
+}
@@ -0,0 +1,40 @@
+use core::ops::Bound;
+use core::ops::Range;
+use core::ops::RangeBounds;
+use Error;
+
+/// Converts any range into a half open range within `0..length`
+pub fn bounds(range: impl RangeBounds<usize>, length: usize) -> Result<Range<usize>, Error> {
+	let start = match range.start_bound() {
+		Bound::Included(&start) => start,
+		Bound::Excluded(&start) => start.checked_add(1).ok_or(Error::OutOfBounds { index: start, length })?,
+		Bound::Unbounded => 0,
+	};
+
+	let end = match range.end_bound() {
+		Bound::Included(&end) => end.checked_add(1).ok_or(Error::OutOfBounds { index: end, length })?,
+		Bound::Excluded(&end) => end,
+		Bound::Unbounded => length,
+	};
+
+	if start > end { return Err(Error::InvalidRange { start, end }); }
+	if end > length { return Err(Error::OutOfBounds { index: end - 1, length }); }
+	Ok(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_bounds() {
+		assert_eq!(bounds(.., 5), Ok(0..5));
+		assert_eq!(bounds(1..=3, 5), Ok(1..4));
+		assert_eq!(bounds(2..2, 5), Ok(2..2));
+		assert_eq!(bounds(5.., 5), Ok(5..5));
+		let (start, end) = (3, 1);
+		assert_eq!(bounds(start..end, 5), Err(Error::InvalidRange { start, end }));
+		assert_eq!(bounds(..=5, 5), Err(Error::OutOfBounds { index: 5, length: 5 }));
+		assert_eq!(bounds(..=usize::MAX, 5), Err(Error::OutOfBounds { index: usize::MAX, length: 5 }));
+	}
+}
@@ -0,0 +1,211 @@
+use super::BackingTree;
+use super::MutableBackingTree;
+use super::PreOrder;
+use Vec;
+
+struct ArenaNode<T> {
+	/// Absent only for the root before it is set
+	value: Option<T>,
+	parent: Option<usize>,
+	children: Vec<usize>,
+}
+
+/// A `BackingTree` where every node can have any number of children
+///
+/// Nodes are stored in a single vector and refer to each other by index.
+/// The slots of removed nodes are reused by later insertions so identifiers
+/// of removed nodes must not be used again. Detached subtrees stay in the
+/// arena until they are attached again or removed.
+pub struct ArenaTree<T> {
+	nodes: Vec<Option<ArenaNode<T>>>,
+	free: Vec<usize>,
+}
+
+impl<T> ArenaTree<T> {
+	pub fn new() -> ArenaTree<T> {
+		ArenaTree {
+			nodes: vec![Some(ArenaNode { value: None, parent: None, children: Vec::new() })],
+			free: Vec::new(),
+		}
+	}
+
+	fn node(&self, node: usize) -> Option<&ArenaNode<T>> {
+		self.nodes.get(node).and_then(|node| node.as_ref())
+	}
+
+	fn node_mut(&mut self, node: usize) -> Option<&mut ArenaNode<T>> {
+		self.nodes.get_mut(node).and_then(|node| node.as_mut())
+	}
+
+	fn allocate(&mut self, node: ArenaNode<T>) -> usize {
+		match self.free.pop() {
+			Some(index) => {
+				self.nodes[index] = Some(node);
+				index
+			}
+			None => {
+				self.nodes.push(Some(node));
+				self.nodes.len() - 1
+			}
+		}
+	}
+
+	/// Attaches `node` as the child at `offset` of `parent`
+	fn attach(&mut self, node: usize, parent: usize, offset: usize) {
+		let children = &mut self.nodes[parent].as_mut().unwrap().children;
+		assert!(offset <= children.len());
+		children.insert(offset, node);
+		self.nodes[node].as_mut().unwrap().parent = Some(parent);
+	}
+}
+
+impl<T> Default for ArenaTree<T> {
+	fn default() -> ArenaTree<T> {
+		ArenaTree::new()
+	}
+}
+
+impl<T> BackingTree for ArenaTree<T> {
+	type Value = T;
+	type Identifier = usize;
+	type ChildIterator = <Vec<usize> as IntoIterator>::IntoIter;
+
+	fn root(&self) -> usize {
+		0
+	}
+
+	fn set_root(&mut self, value: T) {
+		self.nodes[0].as_mut().unwrap().value = Some(value);
+	}
+
+	/// Returns the node itself if it is a root or is detached
+	fn parent(&self, node: &usize) -> usize {
+		self.node(*node).and_then(|current| current.parent).unwrap_or(*node)
+	}
+
+	fn child(&self, node: &usize, child_index: usize) -> usize {
+		self.node(*node).unwrap().children[child_index]
+	}
+
+	fn children(&self, node: &usize) -> <Self as BackingTree>::ChildIterator {
+		self.node(*node).map(|node| node.children.clone()).unwrap_or_default().into_iter()
+	}
+
+	fn get(&self, node: &usize) -> Option<&T> {
+		self.node(*node).and_then(|node| node.value.as_ref())
+	}
+
+	fn get_mut(&mut self, node: &usize) -> Option<&mut T> {
+		self.node_mut(*node).and_then(|node| node.value.as_mut())
+	}
+
+	/// # Panics
+	///
+	/// `offset` must not be greater than the number of children at `node`
+	fn insert_child(&mut self, node: usize, offset: usize, value: T) -> Option<usize> {
+		self.get(&node)?;
+		let child = self.allocate(ArenaNode { value: Some(value), parent: None, children: Vec::new() });
+		self.attach(child, node, offset);
+		Some(child)
+	}
+}
+
+impl<T> MutableBackingTree for ArenaTree<T> {
+	/// Removing the root leaves an empty root that can be set again
+	fn remove_subtree(&mut self, node: &usize) -> Vec<T> {
+		let removed: Vec<usize> = PreOrder::new(self, *node).collect();
+		self.detach(node);
+
+		let values = removed.iter().filter_map(|node| self.nodes[*node].take()?.value).collect();
+		self.free.extend(removed.into_iter().filter(|node| *node != 0));
+		if self.nodes[0].is_none() {
+			self.nodes[0] = Some(ArenaNode { value: None, parent: None, children: Vec::new() });
+		}
+		values
+	}
+
+	fn detach(&mut self, node: &usize) -> bool {
+		let parent = match self.node(*node).and_then(|current| current.parent) {
+			Some(parent) => parent,
+			None => return false,
+		};
+
+		self.nodes[parent].as_mut().unwrap().children.retain(|child| child != node);
+		self.nodes[*node].as_mut().unwrap().parent = None;
+		true
+	}
+
+	fn reparent(&mut self, node: &usize, parent: &usize, offset: usize) -> bool {
+		if self.get(node).is_none() || self.get(parent).is_none() { return false; }
+
+		// The new parent cannot be within the subtree that is being moved
+		let mut ancestor = Some(*parent);
+		while let Some(current) = ancestor {
+			if current == *node { return false; }
+			ancestor = self.nodes[current].as_ref().unwrap().parent;
+		}
+
+		self.detach(node);
+		self.attach(*node, *parent, offset);
+		true
+	}
+}
+
+impl<T> ::core::fmt::Debug for ArenaTree<T> where T: ::core::fmt::Debug {
+	fn fmt(&self, f: &mut ::core::fmt::Formatter) -> Result<(), ::core::fmt::Error> {
+		write!(f, "ArenaTree: [")?;
+		for node in self.nodes.iter().flatten() {
+			write!(f, "({:?}, {:?}, {:?}), ", node.value, node.parent, node.children)?;
+		}
+		writeln!(f, "]")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test() {
+		use tree::LevelOrder;
+		use tree::PostOrder;
+
+		let mut tree = ArenaTree::new();
+		assert_eq!(tree.insert_child(0, 0, 'x'), None);
+		tree.set_root('a');
+		let b = tree.insert_child(0, 0, 'b').unwrap();
+		let d = tree.insert_child(0, 1, 'd').unwrap();
+		let c = tree.insert_child(0, 1, 'c').unwrap();
+		let e = tree.insert_child(b, 0, 'e').unwrap();
+		let f = tree.insert_child(b, 1, 'f').unwrap();
+		tree.insert_child(c, 0, 'g').unwrap();
+		assert_eq!(tree.parent(&e), b);
+		assert_eq!(tree.child(&0, 2), d);
+
+		let values = |tree: &ArenaTree<char>, nodes: &mut dyn Iterator<Item=usize>| -> Vec<char> {
+			nodes.map(|node| *tree.get(&node).unwrap()).collect()
+		};
+		assert_eq!(values(&tree, &mut PreOrder::new(&tree, 0)), ['a', 'b', 'e', 'f', 'c', 'g', 'd']);
+		assert_eq!(values(&tree, &mut PostOrder::new(&tree, 0)), ['e', 'f', 'b', 'g', 'c', 'd', 'a']);
+		assert_eq!(values(&tree, &mut LevelOrder::new(&tree, 0)), ['a', 'b', 'c', 'd', 'e', 'f', 'g']);
+
+		assert!(!tree.reparent(&b, &e, 0));
+		assert!(tree.reparent(&b, &d, 0));
+		assert_eq!(values(&tree, &mut PreOrder::new(&tree, 0)), ['a', 'c', 'g', 'd', 'b', 'e', 'f']);
+
+		assert!(tree.detach(&c));
+		assert!(!tree.detach(&c));
+		assert_eq!(values(&tree, &mut PreOrder::new(&tree, 0)), ['a', 'd', 'b', 'e', 'f']);
+		assert_eq!(tree.parent(&c), c);
+		assert!(tree.reparent(&c, &f, 0));
+
+		assert_eq!(tree.remove_subtree(&b), ['b', 'e', 'f', 'c', 'g']);
+		assert_eq!(tree.get(&e), None);
+		assert_eq!(values(&tree, &mut PreOrder::new(&tree, 0)), ['a', 'd']);
+		let h = tree.insert_child(d, 0, 'h').unwrap();
+		assert!(h != d && tree.get(&h) == Some(&'h'));
+
+		assert_eq!(tree.remove_subtree(&0), ['a', 'd', 'h']);
+		assert_eq!(tree.get(&0), None);
+	}
+}
@@ -1,3 +1,5 @@
+use Vec;
+
 pub trait BackingTree {
 	type Value;
 	type Identifier;
@@ -30,3 +32,27 @@ pub trait BackingTree {
 	/// Returns `None` if `node` does not exist
 	fn insert_child(&mut self, node: Self::Identifier, offset: usize, value: Self::Value) -> Option<Self::Identifier>;
 }
+
+/// A `BackingTree` that allows nodes to be removed and moved
+pub trait MutableBackingTree: BackingTree {
+	/// Removes a node and all of its descendants and returns their values in pre-order
+	///
+	/// Nothing is removed if `node` does not exist.
+	fn remove_subtree(&mut self, node: &Self::Identifier) -> Vec<Self::Value>;
+
+	/// Separates a node and its descendants from its parent
+	///
+	/// The node keeps its identifier and can be attached again with `reparent`.
+	/// Returns false if `node` does not exist or has no parent.
+	fn detach(&mut self, node: &Self::Identifier) -> bool;
+
+	/// Moves a node and its descendants so that it becomes the child at `offset` of `parent`
+	///
+	/// Returns false if either node does not exist or if `parent` is
+	/// `node` or one of its descendants.
+	///
+	/// # Panics
+	///
+	/// `offset` must not be greater than the number of children at `parent`
+	fn reparent(&mut self, node: &Self::Identifier, parent: &Self::Identifier, offset: usize) -> bool;
+}
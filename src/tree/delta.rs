@@ -13,6 +13,18 @@ pub trait DeltaSifter<T, D>: Fn(&T, &D, usize) -> T {}
 
 impl<F, T, D> DeltaSifter<T, D> for F where F: ?Sized + Fn(&T, &D, usize) -> T {}
 
+/// Represents a closure that merges a pending delta with a newer delta
+///
+/// Sifting the merged delta must produce the same value as sifting the
+/// pending delta and then the newer delta.
+///
+/// # Arguments
+/// * The pending delta
+/// * The newer delta
+pub trait DeltaComposer<D>: Fn(&D, &D) -> D {}
+
+impl<F, D> DeltaComposer<D> for F where F: ?Sized + Fn(&D, &D) -> D {}
+
 pub struct DeltaWrapper<T, D> {
 	pub(crate) object: UnsafeCell<T>,
 	pub(crate) delta: RefCell<Option<D>>,
@@ -25,6 +37,11 @@ impl<T, D> DeltaWrapper<T, D> {
 			delta: RefCell::new(None),
 		}
 	}
+
+	/// Unwraps the object discarding any pending delta
+	pub fn into_inner(self) -> T {
+		self.object.into_inner()
+	}
 }
 
 impl<T, D> Deref for DeltaWrapper<T, D> {
@@ -41,6 +58,26 @@ impl<T, D> ::core::ops::DerefMut for DeltaWrapper<T, D> {
 	}
 }
 
+/// The object is serialized together with its pending delta
+#[cfg(feature = "serde")]
+impl<T, D> ::serde::Serialize for DeltaWrapper<T, D> where T: ::serde::Serialize, D: ::serde::Serialize {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: ::serde::Serializer {
+		(self.deref(), self.delta.borrow().deref()).serialize(serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, D> ::serde::Deserialize<'de> for DeltaWrapper<T, D>
+	where T: ::serde::Deserialize<'de>, D: ::serde::Deserialize<'de> {
+	fn deserialize<E>(deserializer: E) -> Result<Self, E::Error> where E: ::serde::Deserializer<'de> {
+		let (object, delta) = ::serde::Deserialize::deserialize(deserializer)?;
+		Ok(DeltaWrapper {
+			object: UnsafeCell::new(object),
+			delta: RefCell::new(delta),
+		})
+	}
+}
+
 impl<T, D> Debug for DeltaWrapper<T, D> where T: Debug, D: Debug {
 	fn fmt(&self, f: &mut ::core::fmt::Formatter) -> Result<(), ::core::fmt::Error> {
 		writeln!(f, "DeltaWrapper {{")?;
@@ -0,0 +1,203 @@
+use core::ops::Bound;
+use core::ops::RangeBounds;
+use operator::AssociativeOperator;
+use OwnedRef;
+use Vec;
+
+struct DynamicNode<T> {
+	value: T,
+	children: [Option<usize>; 2],
+}
+
+/// A variant of `SegmentTree` that only creates nodes when they are needed
+///
+/// `DynamicSegmentTree` covers an arbitrary `u64` coordinate range such as
+/// timestamps or identifiers without storing a value for every index. Any
+/// index that has not been updated holds the identity value:
+/// - Construct: O(`1`)
+/// - Query: O(`log n`)
+/// - Update: O(`log n`) time and additional space
+///
+/// where `n` is the size of the coordinate range.
+pub struct DynamicSegmentTree<T, O> {
+	nodes: Vec<DynamicNode<T>>,
+	operator: O,
+	identity: T,
+	lower: u64,
+	upper: u64,
+}
+
+impl<T, O> DynamicSegmentTree<T, O> where T: Clone, O: AssociativeOperator<T> {
+	/// Creates a tree covering the range `lower..=upper`
+	///
+	/// `identity` is the value of every index that has not been updated. It
+	/// must satisfy `operator(identity, x) == x` and `operator(x, identity) == x`.
+	///
+	/// # Panics
+	///
+	/// `lower` cannot be greater than `upper`
+	pub fn new(lower: u64, upper: u64, operator: O, identity: T) -> DynamicSegmentTree<T, O> {
+		assert!(lower <= upper);
+		let root = DynamicNode {
+			value: identity.clone(),
+			children: [None, None],
+		};
+
+		DynamicSegmentTree {
+			nodes: vec![root],
+			operator,
+			identity,
+			lower,
+			upper,
+		}
+	}
+
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The start of the range cannot be after its end
+	/// The range must be within the range of the tree
+	pub fn query(&self, range: impl RangeBounds<u64>) -> OwnedRef<'_, T> {
+		// Wider integers allow ranges that end at `u64::MAX` to be half open
+		let start = match range.start_bound() {
+			Bound::Included(&start) => start as u128,
+			Bound::Excluded(&start) => start as u128 + 1,
+			Bound::Unbounded => self.lower as u128,
+		};
+
+		let end = match range.end_bound() {
+			Bound::Included(&end) => end as u128 + 1,
+			Bound::Excluded(&end) => end as u128,
+			Bound::Unbounded => self.upper as u128 + 1,
+		};
+
+		assert!(start <= end, "range start {} is after range end {}", start, end);
+		assert!(self.lower as u128 <= start && end <= self.upper as u128 + 1, "range is outside of the tree");
+		if start == end { return (&self.identity).into(); }
+
+		let (left, right) = (start as u64, (end - 1) as u64);
+		self.query_recursively(0, left, right, self.lower, self.upper)
+		    .unwrap_or_else(|| (&self.identity).into())
+	}
+
+	fn query_recursively(&self, node: usize, left: u64, right: u64,
+	                     current_left: u64, current_right: u64) -> Option<OwnedRef<'_, T>> {
+		let in_range = left <= current_left && current_right <= right;
+		if in_range { return Some((&self.nodes[node].value).into()); }
+
+		// Absent children only hold the identity so they can be skipped
+		let (middle_left, middle_right) = split_range(current_left, current_right);
+		let mut left_value = None;
+		let mut right_value = None;
+		if let Some(left_child) = self.nodes[node].children[0] {
+			if middle_left >= left {
+				left_value = self.query_recursively(left_child, left, right, current_left, middle_left);
+			}
+		}
+		if let Some(right_child) = self.nodes[node].children[1] {
+			if middle_right <= right {
+				right_value = self.query_recursively(right_child, left, right, middle_right, current_right);
+			}
+		}
+
+		match (left_value, right_value) {
+			(Some(left_value), Some(right_value)) => Some((self.operator)(&left_value, &right_value).into()),
+			(left_value, None) => left_value,
+			(None, right_value) => right_value,
+		}
+	}
+
+	/// Updates a value
+	///
+	/// # Panics
+	///
+	/// `index` must be within the range of the tree
+	pub fn update(&mut self, index: u64, value: T) {
+		assert!(self.lower <= index && index <= self.upper);
+		let (lower, upper) = (self.lower, self.upper);
+		self.update_recursively(0, index, value, lower, upper);
+	}
+
+	fn update_recursively(&mut self, node: usize, index: u64, value: T,
+	                      current_left: u64, current_right: u64) {
+		if current_left == current_right {
+			self.nodes[node].value = value;
+			return;
+		}
+
+		let (middle_left, middle_right) = split_range(current_left, current_right);
+		if index <= middle_left {
+			let left_child = self.child_or_insert(node, 0);
+			self.update_recursively(left_child, index, value, current_left, middle_left);
+		} else {
+			let right_child = self.child_or_insert(node, 1);
+			self.update_recursively(right_child, index, value, middle_right, current_right);
+		}
+
+		let value = {
+			let children = self.nodes[node].children;
+			let left_child = children[0].map(|child| &self.nodes[child].value).unwrap_or(&self.identity);
+			let right_child = children[1].map(|child| &self.nodes[child].value).unwrap_or(&self.identity);
+			(self.operator)(left_child, right_child)
+		};
+		self.nodes[node].value = value;
+	}
+
+	fn child_or_insert(&mut self, node: usize, child_index: usize) -> usize {
+		if let Some(child) = self.nodes[node].children[child_index] {
+			return child;
+		}
+
+		let child = self.nodes.len();
+		let value = self.identity.clone();
+		self.nodes.push(DynamicNode {
+			value,
+			children: [None, None],
+		});
+		self.nodes[node].children[child_index] = Some(child);
+		child
+	}
+}
+
+/// Splits a range in the same way as `functions::split_range` without
+/// overflowing near `u64::MAX`
+fn split_range(left: u64, right: u64) -> (u64, u64) {
+	let middle_left = left + (right - left) / 2;
+	(middle_left, middle_left + 1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_summation() {
+		use core::ops::Deref;
+		let mut tree = DynamicSegmentTree::new(0, u64::MAX, ::operator::summation(), 0u64);
+		assert_eq!(*tree.query(..).deref(), 0);
+		tree.update(1_500_000_000_000, 3);
+		tree.update(1_500_000_000_007, 4);
+		tree.update(u64::MAX, 5);
+		assert_eq!(*tree.query(..).deref(), 3 + 4 + 5);
+		assert_eq!(*tree.query(1_500_000_000_000..1_500_000_000_007).deref(), 3);
+		assert_eq!(*tree.query(1_500_000_000_001..=1_500_000_000_007).deref(), 4);
+		assert_eq!(*tree.query(1_500_000_000_008..u64::MAX).deref(), 0);
+		tree.update(1_500_000_000_000, 10);
+		assert_eq!(*tree.query(..=1_500_000_000_007).deref(), 10 + 4);
+	}
+
+	#[test]
+	fn test_minimum() {
+		use core::ops::Deref;
+		let mut tree = DynamicSegmentTree::new(100, 200, ::operator::minimum(), u32::MAX);
+		tree.update(150, 7);
+		tree.update(120, 9);
+		assert_eq!(*tree.query(..).deref(), 7);
+		assert_eq!(*tree.query(..150).deref(), 9);
+		assert_eq!(*tree.query(151..=200).deref(), u32::MAX);
+		assert_eq!(*tree.query(150..150).deref(), u32::MAX);
+	}
+}
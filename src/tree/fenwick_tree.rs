@@ -0,0 +1,334 @@
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use operator::Monoid;
+use operator::InverseOperator;
+use OwnedRef;
+use Vec;
+
+/// `FenwickTree` efficiently calculates prefix values of an array
+///
+/// Also known as a binary indexed tree. Every position stores the value of
+/// a range whose length is the lowest set bit of its one based index. It
+/// uses a single array the length of the input and is faster than a
+/// `SegmentTree` for prefix queries:
+/// - Construct: O(`n`)
+/// - Prefix query: O(`log n`)
+/// - Update: O(`log n`)
+///
+/// The operator must be commutative. Arbitrary ranges are answered by
+/// removing one prefix from another with the inverse operator.
+pub struct FenwickTree<T, O, I> {
+	tree: Vec<T>,
+	operator: O,
+	inverse: I,
+}
+
+impl<T, O, I> FenwickTree<T, O, I> where O: Monoid<T>, I: InverseOperator<T> {
+	pub fn construct(values: impl FixedDataSource<T>, operator: O, inverse: I) -> FenwickTree<T, O, I> {
+		let mut tree: Vec<T> = values.collect();
+		for position in 1..=tree.len() {
+			let parent = position + lowest_bit(position);
+			if parent <= tree.len() {
+				let value = operator.operate(&tree[position - 1], &tree[parent - 1]);
+				tree[parent - 1] = value;
+			}
+		}
+
+		FenwickTree {
+			tree,
+			operator,
+			inverse,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.tree.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.tree.is_empty()
+	}
+
+	/// Get the value for the range `0..=index`
+	///
+	/// # Panics
+	///
+	/// `index` must be less than the length of the input array
+	pub fn prefix(&self, index: usize) -> OwnedRef<'_, T> {
+		assert!(index < self.tree.len());
+		let mut position = index + 1;
+		let mut value: Option<OwnedRef<'_, T>> = None;
+		while position > 0 {
+			let node = &self.tree[position - 1];
+			value = Some(match value {
+				Some(value) => self.operator.operate(node, &value).into(),
+				None => node.into(),
+			});
+			position -= lowest_bit(position);
+		}
+		value.unwrap()
+	}
+
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	/// The operator must have an identity if the range is empty
+	pub fn query(&self, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if it is empty and the operator has no identity
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		let range = ::range::bounds(range, self.tree.len())?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		Ok(match range.start {
+			0 => self.prefix(range.end - 1),
+			start => (self.inverse)(&self.prefix(range.end - 1), &self.prefix(start - 1)).into(),
+		})
+	}
+
+	/// Combines `delta` into the value at `index`
+	///
+	/// # Panics
+	///
+	/// `index` must be less than the length of the input array
+	pub fn update(&mut self, index: usize, delta: &T) {
+		assert!(index < self.tree.len());
+		let mut position = index + 1;
+		while position <= self.tree.len() {
+			let value = self.operator.operate(&self.tree[position - 1], delta);
+			self.tree[position - 1] = value;
+			position += lowest_bit(position);
+		}
+	}
+
+	/// Finds the first `index` such that `predicate` holds for the range `0..=index`
+	///
+	/// `predicate` must be monotone: once it holds for a prefix it must hold
+	/// for every longer prefix. This takes O(`log n`).
+	///
+	/// # Errors
+	///
+	/// Returns `None` if `predicate` does not hold for any prefix
+	pub fn lower_bound<F>(&self, predicate: F) -> Option<usize> where F: Fn(&T) -> bool {
+		let mut position = 0;
+		let mut value: Option<OwnedRef<'_, T>> = None;
+		let mut step = match self.tree.len() {
+			0 => 0,
+			length => 1 << ::math::integer_log2(length as u64),
+		};
+
+		while step > 0 {
+			let next = position + step;
+			if next <= self.tree.len() {
+				let node = &self.tree[next - 1];
+				let candidate: OwnedRef<'_, T> = match value {
+					Some(ref value) => self.operator.operate(value, node).into(),
+					None => node.into(),
+				};
+
+				if !predicate(&candidate) {
+					position = next;
+					value = Some(candidate);
+				}
+			}
+			step >>= 1;
+		}
+
+		if position < self.tree.len() { Some(position) } else { None }
+	}
+}
+
+/// A variant of `FenwickTree` that allows range updates
+///
+/// Two additional trees store the updates as a linear function of the
+/// prefix length. Multiplying a value by a length is done by repeated
+/// doubling with the operator so no numeric traits are needed. The operator
+/// must have an identity:
+/// - Construct: O(`n`)
+/// - Query: O(`log n`)
+/// - Update: O(`log n`)
+pub struct RangeFenwickTree<T, O, I> {
+	values: FenwickTree<T, O, I>,
+	linear: FenwickTree<T, O, I>,
+	constant: FenwickTree<T, O, I>,
+}
+
+impl<T, O, I> RangeFenwickTree<T, O, I> where O: Monoid<T> + Clone, I: InverseOperator<T> + Clone {
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn construct(values: impl FixedDataSource<T>, operator: O, inverse: I) -> RangeFenwickTree<T, O, I> {
+		assert!(operator.identity().is_some(), "operator has no identity for a range fenwick tree");
+		let length = values.len();
+		let empty = || (0..length).map(|_| operator.identity().unwrap());
+		let linear = FenwickTree::construct(empty(), operator.clone(), inverse.clone());
+		let constant = FenwickTree::construct(empty(), operator.clone(), inverse.clone());
+		RangeFenwickTree {
+			values: FenwickTree::construct(values, operator, inverse),
+			linear,
+			constant,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	/// Get the value for the range `0..=index`
+	///
+	/// # Panics
+	///
+	/// `index` must be less than the length of the input array
+	pub fn prefix(&self, index: usize) -> T {
+		let updates = (self.values.inverse)(&self.scale(&self.linear.prefix(index), index + 1),
+		                                    &self.constant.prefix(index));
+		self.values.operator.operate(&self.values.prefix(index), &updates)
+	}
+
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn query(&self, range: impl RangeBounds<usize>) -> T {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<T, Error> {
+		let range = ::range::bounds(range, self.len())?;
+		Ok(match (range.start, range.end) {
+			(start, end) if start == end => self.identity(),
+			(0, end) => self.prefix(end - 1),
+			(start, end) => (self.values.inverse)(&self.prefix(end - 1), &self.prefix(start - 1)),
+		})
+	}
+
+	/// Combines `delta` into every value in a range
+	///
+	/// Nothing is updated if the range is empty.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn update_range(&mut self, range: impl RangeBounds<usize>, delta: &T) {
+		self.try_update_range(range, delta).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Combines `delta` into every value in a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_update_range(&mut self, range: impl RangeBounds<usize>, delta: &T) -> Result<(), Error> {
+		let range = ::range::bounds(range, self.len())?;
+		if range.is_empty() { return Ok(()); }
+		let (left, right) = (range.start, range.end - 1);
+		let scaled = self.scale(delta, left);
+		self.linear.update(left, delta);
+		self.constant.update(left, &scaled);
+
+		if right + 1 < self.len() {
+			let negated = (self.values.inverse)(&self.identity(), delta);
+			let scaled = self.scale(&negated, right + 1);
+			self.linear.update(right + 1, &negated);
+			self.constant.update(right + 1, &scaled);
+		}
+		Ok(())
+	}
+
+	/// Combines `value` with itself `count` times
+	fn scale(&self, value: &T, mut count: usize) -> T {
+		let operator = &self.values.operator;
+		let mut result = self.identity();
+		let mut power: OwnedRef<'_, T> = value.into();
+		while count > 0 {
+			if count & 1 == 1 { result = operator.operate(&result, &power); }
+			count >>= 1;
+			if count > 0 { power = operator.operate(&power, &power).into(); }
+		}
+		result
+	}
+
+	fn identity(&self) -> T {
+		self.values.operator.identity().expect("operator has no identity for a range fenwick tree")
+	}
+}
+
+fn lowest_bit(position: usize) -> usize {
+	position & position.wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_summation() {
+		use core::ops::Deref;
+		let mut data = vec![3, 1, 4, 1, 5, 9, 2, 6, 5];
+		let mut tree = FenwickTree::construct(data.clone().into_iter(), ::operator::summation(),
+		                                      ::operator::subtraction());
+		assert_eq!(tree.len(), data.len());
+
+		for &(index, delta) in [(0, 0), (4, 3), (8, -2), (0, 7)].iter() {
+			tree.update(index, &delta);
+			data[index] += delta;
+			for right in 0..data.len() {
+				assert_eq!(*tree.prefix(right).deref(), data[..=right].iter().sum::<i32>());
+				for left in 0..=right {
+					assert_eq!(*tree.query(left..=right).deref(), data[left..=right].iter().sum::<i32>());
+				}
+			}
+
+			for limit in 0..60 {
+				let expected = (0..data.len()).find(|index| data[..=*index].iter().sum::<i32>() >= limit);
+				assert_eq!(tree.lower_bound(|sum| *sum >= limit), expected);
+			}
+		}
+		assert_eq!(tree.try_query(3..3).err(), Some(Error::EmptyRange));
+	}
+
+	#[test]
+	fn test_range() {
+		let mut data = vec![3, 1, 4, 1, 5, 9, 2, 6, 5];
+		let mut tree = RangeFenwickTree::construct(data.clone().into_iter(), ::operator::summation_default::<i64>(),
+		                                           ::operator::subtraction());
+
+		for &(left, right, delta) in [(0, 8, 2), (2, 5, -3), (4, 4, 10), (7, 8, 1)].iter() {
+			tree.update_range(left..=right, &delta);
+			data[left..=right].iter_mut().for_each(|value| *value += delta);
+			for right in 0..data.len() {
+				for left in 0..=right {
+					assert_eq!(tree.query(left..=right), data[left..=right].iter().sum::<i64>());
+				}
+			}
+		}
+		assert_eq!(tree.query(4..4), 0);
+		assert_eq!(tree.try_update_range(5..10, &1), Err(Error::OutOfBounds { index: 9, length: 9 }));
+	}
+}
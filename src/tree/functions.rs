@@ -17,6 +17,20 @@ pub fn split_range(left: usize, right: usize) -> (usize, usize) {
 	(middle_left, middle_right)
 }
 
+/// Splits a range into at most `width` consecutive ranges
+///
+/// Every range has the same length except possibly the last. Splitting
+/// into two ranges is the same as `split_range`.
+pub fn split_range_width(left: usize, right: usize, width: usize)
+                         -> impl DoubleEndedIterator<Item=(usize, usize)> + ExactSizeIterator {
+	let chunk = (right - left) / width + 1;
+	let count = (right - left) / chunk + 1;
+	(0..count).map(move |index| {
+		let chunk_left = left + index * chunk;
+		(chunk_left, ::core::cmp::min(chunk_left + chunk - 1, right))
+	})
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -38,4 +52,15 @@ mod tests {
 		assert_eq!(split_range(0, 7), (3, 4));
 		assert_eq!(split_range(10, 19), (14, 15))
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn test_split_range_width() {
+		for right in 4..20 {
+			let (middle_left, middle_right) = split_range(3, right);
+			assert!(split_range_width(3, right, 2).eq(vec![(3, middle_left), (middle_right, right)]));
+		}
+		assert!(split_range_width(0, 9, 4).eq(vec![(0, 2), (3, 5), (6, 8), (9, 9)]));
+		assert!(split_range_width(0, 2, 4).eq(vec![(0, 0), (1, 1), (2, 2)]));
+		assert!(split_range_width(5, 5, 4).eq(vec![(5, 5)]));
+	}
+}
@@ -0,0 +1,523 @@
+use Box;
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use operator::Monoid;
+use OwnedRef;
+use super::DeltaComposer;
+use super::DeltaSifter;
+use Vec;
+
+type Link<T, D> = Option<Box<TreapNode<T, D>>>;
+
+struct TreapNode<T, D> {
+	value: T,
+	/// Absent if the node has no children as the aggregate is then the value
+	aggregate: Option<T>,
+	/// The aggregate of the subtree combined from right to left
+	reversed_aggregate: Option<T>,
+	priority: u64,
+	size: usize,
+	/// Pending delta for the whole subtree including this node
+	delta: Option<D>,
+	/// Whether the whole subtree is pending a reversal
+	reversed: bool,
+	children: [Link<T, D>; 2],
+}
+
+impl<T, D> TreapNode<T, D> {
+	fn aggregate(&self) -> &T {
+		self.aggregate.as_ref().unwrap_or(&self.value)
+	}
+
+	fn reversed_aggregate(&self) -> &T {
+		self.reversed_aggregate.as_ref().unwrap_or(&self.value)
+	}
+}
+
+fn size<T, D>(link: &Link<T, D>) -> usize {
+	link.as_ref().map_or(0, |node| node.size)
+}
+
+/// The closures used to apply pending tags
+struct Tags<S, C> {
+	sifter: S,
+	composer: Option<C>,
+}
+
+impl<S, C> Tags<S, C> {
+	/// Applies the pending tags of a node and passes them to its children
+	fn sift<T, D>(&self, node: &mut TreapNode<T, D>) where S: DeltaSifter<T, D>, D: Clone, C: DeltaComposer<D> {
+		if let Some(delta) = node.delta.take() {
+			node.value = (self.sifter)(&node.value, &delta, 1);
+			if let Some(ref mut aggregate) = node.aggregate {
+				*aggregate = (self.sifter)(aggregate, &delta, node.size);
+			}
+			if let Some(ref mut aggregate) = node.reversed_aggregate {
+				*aggregate = (self.sifter)(aggregate, &delta, node.size);
+			}
+
+			for child in node.children.iter_mut() {
+				if let Some(ref mut child) = *child {
+					self.push_delta(child, &delta);
+				}
+			}
+		}
+
+		if node.reversed {
+			node.reversed = false;
+			node.children.swap(0, 1);
+			::core::mem::swap(&mut node.aggregate, &mut node.reversed_aggregate);
+			for child in node.children.iter_mut() {
+				if let Some(ref mut child) = *child {
+					child.reversed ^= true;
+				}
+			}
+		}
+	}
+
+	fn push_delta<T, D>(&self, node: &mut TreapNode<T, D>, delta: &D) where S: DeltaSifter<T, D>, D: Clone, C: DeltaComposer<D> {
+		if let Some(ref composer) = self.composer {
+			node.delta = Some(match node.delta {
+				Some(ref pending) => composer(pending, delta),
+				None => delta.clone(),
+			});
+		} else {
+			self.sift(node);
+			node.delta = Some(delta.clone());
+		}
+	}
+}
+
+/// A sequence that can be cut, concatenated and reversed
+///
+/// `ImplicitTreap` is a binary search tree keyed by the position of each
+/// value and balanced by random priorities. Unlike `SegmentTree` values can
+/// be inserted or removed anywhere and whole sequences can be split or merged.
+/// Range updates and reversals are kept pending in the same way as
+/// `LazySegmentTree`:
+/// - Construct: O(`n log n`)
+/// - Query: O(`log n`)
+/// - Insert, remove, split and merge: O(`log n`)
+/// - Range update and reverse: O(`log n`)
+///
+/// All bounds are expected rather than worst case.
+///
+/// Every node also keeps the aggregate of its subtree in reverse order so
+/// that a reversal only has to swap the two. The operator does not need to
+/// be commutative but it is applied twice as often.
+pub struct ImplicitTreap<T, O, S, D, C = fn(&D, &D) -> D> {
+	root: Link<T, D>,
+	operator: O,
+	tags: Tags<S, C>,
+	seed: u64,
+}
+
+impl<T, O, S, D> ImplicitTreap<T, O, S, D> where O: Monoid<T>, S: DeltaSifter<T, D>, D: Clone {
+	pub fn construct(values: impl FixedDataSource<T>, operator: O, sifter: S) -> ImplicitTreap<T, O, S, D> {
+		Self::construct_tags(values, operator, Tags { sifter, composer: None })
+	}
+}
+
+impl<T, O, S, D, C> ImplicitTreap<T, O, S, D, C>
+	where O: Monoid<T>, S: DeltaSifter<T, D>, D: Clone, C: DeltaComposer<D> {
+	/// Constructs a treap that merges pending deltas with `composer`
+	///
+	/// See `LazySegmentTree::construct_composed` for the tradeoffs.
+	pub fn construct_composed(values: impl FixedDataSource<T>, operator: O, sifter: S, composer: C)
+	                          -> ImplicitTreap<T, O, S, D, C> {
+		Self::construct_tags(values, operator, Tags { sifter, composer: Some(composer) })
+	}
+
+	fn construct_tags(values: impl FixedDataSource<T>, operator: O, tags: Tags<S, C>) -> ImplicitTreap<T, O, S, D, C> {
+		let mut treap = ImplicitTreap {
+			root: None,
+			operator,
+			tags,
+			seed: 0x2545_f491_4f6c_dd1d,
+		};
+
+		values.for_each(|value| treap.push(value));
+		treap
+	}
+
+	pub fn len(&self) -> usize {
+		size(&self.root)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.root.is_none()
+	}
+
+	/// Appends a value to the end
+	pub fn push(&mut self, value: T) {
+		let node = self.create_node(value);
+		let root = self.root.take();
+		self.root = self.merge_links(root, node);
+	}
+
+	/// Inserts a value so that it is at `index`
+	///
+	/// # Panics
+	///
+	/// `index` cannot be greater than the length of the treap
+	pub fn insert(&mut self, index: usize, value: T) {
+		assert!(index <= self.len());
+		let node = self.create_node(value);
+		let root = self.root.take();
+		let (left, right) = self.split_link(root, index);
+		let left = self.merge_links(left, node);
+		self.root = self.merge_links(left, right);
+	}
+
+	/// Removes and returns the value at `index`
+	///
+	/// # Panics
+	///
+	/// `index` must be less than the length of the treap
+	pub fn remove(&mut self, index: usize) -> T {
+		assert!(index < self.len());
+		let root = self.root.take();
+		let (left, right) = self.split_link(root, index);
+		let (middle, right) = self.split_link(right, 1);
+		self.root = self.merge_links(left, right);
+
+		let mut middle = middle.unwrap();
+		self.tags.sift(&mut middle);
+		middle.value
+	}
+
+	/// Appends every value of `other` to the end
+	///
+	/// The closures of `other` are discarded.
+	pub fn merge(mut self, mut other: ImplicitTreap<T, O, S, D, C>) -> ImplicitTreap<T, O, S, D, C> {
+		let root = self.root.take();
+		self.root = self.merge_links(root, other.root.take());
+		self
+	}
+
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The range must be within the treap
+	/// The operator must have an identity if the range is empty
+	pub fn query(&mut self, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the treap or
+	/// if it is empty and the operator has no identity
+	pub fn try_query(&mut self, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		let range = ::range::bounds(range, self.len())?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		let root = self.root.as_mut().unwrap();
+		Ok(Self::query_recursively(&self.operator, &self.tags, root, range.start, range.end))
+	}
+
+	/// Combines the values in `left..right` relative to the subtree of `node`
+	fn query_recursively<'a>(operator: &O, tags: &Tags<S, C>, node: &'a mut TreapNode<T, D>,
+	                         left: usize, right: usize) -> OwnedRef<'a, T> {
+		tags.sift(node);
+		if left == 0 && right == node.size {
+			let node: &'a TreapNode<T, D> = node;
+			return node.aggregate().into();
+		}
+
+		let TreapNode { ref value, ref mut children, .. } = *node;
+		let left_size = size(&children[0]);
+		let (left_child, right_child) = children.split_at_mut(1);
+		let mut result: Option<OwnedRef<'_, T>> = None;
+		if left < left_size {
+			let child = left_child[0].as_mut().unwrap();
+			result = Some(Self::query_recursively(operator, tags, child, left, right.min(left_size)));
+		}
+
+		if left <= left_size && left_size < right {
+			result = Some(match result {
+				Some(result) => operator.operate(&result, value).into(),
+				None => value.into(),
+			});
+		}
+
+		if right > left_size + 1 {
+			let child = right_child[0].as_mut().unwrap();
+			let child_left = left.saturating_sub(left_size + 1);
+			let child_value = Self::query_recursively(operator, tags, child, child_left, right - left_size - 1);
+			result = Some(match result {
+				Some(result) => operator.operate(&result, &child_value).into(),
+				None => child_value,
+			});
+		}
+		result.unwrap()
+	}
+
+	/// Reverses the order of the values in a range
+	///
+	/// # Panics
+	///
+	/// The range must be within the treap
+	pub fn reverse(&mut self, range: impl RangeBounds<usize>) {
+		let range = ::range::bounds(range, self.len()).unwrap_or_else(|error| panic!("{}", error));
+		self.modify_range(range, |_, node| node.reversed ^= true);
+	}
+
+	/// Applies `delta` to every value in a range
+	///
+	/// # Panics
+	///
+	/// The range must be within the treap
+	pub fn update_range(&mut self, range: impl RangeBounds<usize>, delta: &D) {
+		let range = ::range::bounds(range, self.len()).unwrap_or_else(|error| panic!("{}", error));
+		self.modify_range(range, |tags, node| tags.push_delta(node, delta));
+	}
+
+	fn modify_range<F>(&mut self, range: ::core::ops::Range<usize>, modifier: F)
+		where F: FnOnce(&Tags<S, C>, &mut TreapNode<T, D>) {
+		if range.is_empty() { return; }
+		let root = self.root.take();
+		let (left, right) = self.split_link(root, range.start);
+		let (mut middle, right) = self.split_link(right, range.end - range.start);
+		modifier(&self.tags, middle.as_mut().unwrap());
+		let left = self.merge_links(left, middle);
+		self.root = self.merge_links(left, right);
+	}
+
+	/// Consumes the treap and returns the values in order
+	pub fn into_vec(mut self) -> Vec<T> {
+		let mut values = Vec::with_capacity(self.len());
+		let root = self.root.take();
+		self.take_values(root, &mut values);
+		values
+	}
+
+	fn take_values(&self, link: Link<T, D>, values: &mut Vec<T>) {
+		if let Some(mut node) = link {
+			self.tags.sift(&mut node);
+			let node = *node;
+			let [left, right] = node.children;
+			self.take_values(left, values);
+			values.push(node.value);
+			self.take_values(right, values);
+		}
+	}
+
+	/// Creates a node with a pseudorandom priority
+	fn create_node(&mut self, value: T) -> Link<T, D> {
+		// Xorshift is sufficient as priorities only need to look random
+		self.seed ^= self.seed << 13;
+		self.seed ^= self.seed >> 7;
+		self.seed ^= self.seed << 17;
+		Some(Box::new(TreapNode {
+			value,
+			aggregate: None,
+			reversed_aggregate: None,
+			priority: self.seed,
+			size: 1,
+			delta: None,
+			reversed: false,
+			children: [None, None],
+		}))
+	}
+
+	/// Splits a subtree into its first `index` values and the remaining values
+	fn split_link(&self, link: Link<T, D>, index: usize) -> (Link<T, D>, Link<T, D>) {
+		let mut node = match link {
+			Some(node) => node,
+			None => return (None, None),
+		};
+
+		self.tags.sift(&mut node);
+		let left_size = size(&node.children[0]);
+		if index <= left_size {
+			let (left, right) = self.split_link(node.children[0].take(), index);
+			node.children[0] = right;
+			self.pull_node(&mut node);
+			(left, Some(node))
+		} else {
+			let (left, right) = self.split_link(node.children[1].take(), index - left_size - 1);
+			node.children[1] = left;
+			self.pull_node(&mut node);
+			(Some(node), right)
+		}
+	}
+
+	/// Concatenates two subtrees
+	fn merge_links(&self, left: Link<T, D>, right: Link<T, D>) -> Link<T, D> {
+		match (left, right) {
+			(None, link) | (link, None) => link,
+			(Some(mut left), Some(mut right)) => {
+				if left.priority > right.priority {
+					self.tags.sift(&mut left);
+					let child = left.children[1].take();
+					left.children[1] = self.merge_links(child, Some(right));
+					self.pull_node(&mut left);
+					Some(left)
+				} else {
+					self.tags.sift(&mut right);
+					let child = right.children[0].take();
+					right.children[0] = self.merge_links(Some(left), child);
+					self.pull_node(&mut right);
+					Some(right)
+				}
+			}
+		}
+	}
+
+	/// Recomputes the size and aggregates of a node from its children
+	///
+	/// The children are sifted first as their aggregates do not include their pending tags.
+	fn pull_node(&self, node: &mut TreapNode<T, D>) {
+		for child in node.children.iter_mut() {
+			if let Some(ref mut child) = *child {
+				self.tags.sift(child);
+			}
+		}
+
+		node.size = 1 + size(&node.children[0]) + size(&node.children[1]);
+		let [ref left, ref right] = node.children;
+		node.aggregate = self.combine(left.as_ref().map(|left| left.aggregate()), &node.value,
+		                              right.as_ref().map(|right| right.aggregate()));
+		node.reversed_aggregate = self.combine(right.as_ref().map(|right| right.reversed_aggregate()), &node.value,
+		                                       left.as_ref().map(|left| left.reversed_aggregate()));
+	}
+
+	/// Combines a value between two optional aggregates
+	///
+	/// Returns `None` if both aggregates are absent.
+	fn combine(&self, left: Option<&T>, value: &T, right: Option<&T>) -> Option<T> {
+		let aggregate = left.map(|left| self.operator.operate(left, value));
+		match (aggregate, right) {
+			(Some(aggregate), Some(right)) => Some(self.operator.operate(&aggregate, right)),
+			(None, Some(right)) => Some(self.operator.operate(value, right)),
+			(aggregate, None) => aggregate,
+		}
+	}
+}
+
+impl<T, O, S, D, C> ImplicitTreap<T, O, S, D, C>
+	where O: Monoid<T> + Clone, S: DeltaSifter<T, D> + Clone, D: Clone, C: DeltaComposer<D> + Clone {
+	/// Splits the treap into the values before `index` and the remaining values
+	///
+	/// # Panics
+	///
+	/// `index` cannot be greater than the length of the treap
+	pub fn split_at(mut self, index: usize) -> (Self, Self) {
+		assert!(index <= self.len());
+		let root = self.root.take();
+		let (left, right) = self.split_link(root, index);
+		let other = ImplicitTreap {
+			root: right,
+			operator: self.operator.clone(),
+			tags: Tags {
+				sifter: self.tags.sifter.clone(),
+				composer: self.tags.composer.clone(),
+			},
+			seed: self.seed.rotate_left(32),
+		};
+
+		self.root = left;
+		(self, other)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sequence() {
+		use core::ops::Deref;
+		let sifter = |current: &i64, delta: &i64, length: usize| *current + (*delta * length as i64);
+		let mut data: Vec<i64> = (1..=12).collect();
+		let mut treap = ImplicitTreap::construct(data.clone().into_iter(), ::operator::summation_default(), sifter);
+		assert_eq!(*treap.query(3..3).deref(), 0);
+
+		for step in 0..40 {
+			let length = data.len();
+			let (left, right) = ((step * 7) % length, (step * 11) % length);
+			let (left, right) = if left <= right { (left, right) } else { (right, left) };
+			match step % 4 {
+				0 => {
+					treap.reverse(left..=right);
+					data[left..=right].reverse();
+				}
+				1 => {
+					treap.update_range(left..=right, &(step as i64));
+					data[left..=right].iter_mut().for_each(|value| *value += step as i64);
+				}
+				2 => {
+					treap.insert(left, -(step as i64));
+					data.insert(left, -(step as i64));
+				}
+				_ => assert_eq!(treap.remove(right), data.remove(right)),
+			}
+
+			assert_eq!(treap.len(), data.len());
+			for right in 0..data.len() {
+				for left in 0..=right {
+					assert_eq!(*treap.query(left..=right).deref(), data[left..=right].iter().sum::<i64>());
+				}
+			}
+		}
+		assert_eq!(treap.into_vec(), data);
+	}
+
+	#[test]
+	fn test_split_merge() {
+		// Deltas are affine maps `x -> a * x + b`
+		let sifter = |current: &i64, delta: &(i64, i64), length: usize| {
+			delta.0 * *current + delta.1 * length as i64
+		};
+		let composer = |pending: &(i64, i64), delta: &(i64, i64)| {
+			(delta.0 * pending.0, delta.0 * pending.1 + delta.1)
+		};
+
+		let data: Vec<i64> = (0..10).collect();
+		let operator = |a: &i64, b: &i64| a + b;
+		let mut treap = ImplicitTreap::construct_composed(data.into_iter(), operator, sifter, composer);
+		treap.update_range(.., &(2, 1));
+		treap.reverse(..5);
+		let (mut left, mut right) = treap.split_at(4);
+		assert_eq!(*left.query(..), 9 + 7 + 5 + 3);
+		right.update_range(..2, &(1, -1));
+		right.reverse(..);
+
+		let treap = right.merge(left);
+		assert_eq!(treap.into_vec(), vec![19, 17, 15, 13, 10, 0, 9, 7, 5, 3]);
+	}
+
+	#[test]
+	fn test_reverse_order() {
+		use core::ops::Deref;
+
+		// Digits are concatenated so the order of every query is visible
+		let operator = |a: &(u64, u64), b: &(u64, u64)| (a.0 * b.1 + b.0, a.1 * b.1);
+		let sifter = |current: &(u64, u64), _: &(), _: usize| *current;
+		let mut data: Vec<(u64, u64)> = (1..=9).map(|digit| (digit, 10)).collect();
+		let mut treap = ImplicitTreap::construct(data.clone().into_iter(), operator, sifter);
+
+		for step in 0..30 {
+			let (left, right) = ((step * 7) % 9, (step * 4) % 9);
+			let (left, right) = if left <= right { (left, right) } else { (right, left) };
+			treap.reverse(left..=right);
+			data[left..=right].reverse();
+
+			for right in 0..data.len() {
+				for left in 0..=right {
+					let expected = data[left + 1..=right].iter().fold(data[left], |a, b| operator(&a, b));
+					assert_eq!(*treap.query(left..=right).deref(), expected);
+				}
+			}
+		}
+	}
+}
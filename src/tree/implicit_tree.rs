@@ -1,6 +1,7 @@
 use super::BackingTree;
 use Vec;
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ImplicitTree<T> {
 	values: Vec<Option<T>>,
 	width: usize,
@@ -14,6 +15,10 @@ impl<T> ImplicitTree<T> {
 		}
 	}
 
+	pub fn width(&self) -> usize {
+		self.width
+	}
+
 	/// Inserts a node into the tree ignoring checks
 	///
 	/// # Safety
@@ -24,6 +29,16 @@ impl<T> ImplicitTree<T> {
 		super::functions::extend_inclusive(&mut self.values, node, &mut || None);
 		self.values[node] = Some(value);
 	}
+
+	/// Removes a node from the tree ignoring checks
+	///
+	/// # Safety
+	///
+	/// Removing a node can break the invariant that every valid child has
+	/// a valid parent
+	pub unsafe fn remove_unchecked(&mut self, node: usize) -> Option<T> {
+		self.values.get_mut(node).and_then(|node| node.take())
+	}
 }
 
 impl<T> BackingTree for ImplicitTree<T> {
@@ -41,7 +56,7 @@ impl<T> BackingTree for ImplicitTree<T> {
 	}
 
 	fn parent(&self, node: &usize) -> usize {
-		(node - 1) / 2
+		(node - 1) / self.width
 	}
 
 	fn child(&self, node: &usize, child_index: usize) -> usize {
@@ -50,7 +65,8 @@ impl<T> BackingTree for ImplicitTree<T> {
 	}
 
 	fn children(&self, node: &<Self as BackingTree>::Identifier) -> <Self as BackingTree>::ChildIterator {
-		self.child(node, 0)..self.child(node, self.width)
+		let first_child = self.child(node, 0);
+		first_child..first_child + self.width
 	}
 
 	fn get(&self, node: &usize) -> Option<&T> {
@@ -104,4 +120,28 @@ mod tests {
 		unsafe { tree.insert_unchecked(3, 4); }
 		assert_eq!(tree.get(&3), Some(&4));
 	}
+
+	#[test]
+	fn test_width() {
+		let tree: ImplicitTree<()> = ImplicitTree::new(3);
+		assert!(tree.children(&0).eq(1..4));
+		assert!(tree.children(&2).eq(7..10));
+		for node in 1..40 {
+			assert!(tree.children(&tree.parent(&node)).any(|child| child == node));
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_serde() {
+		let mut tree = ImplicitTree::new(3);
+		tree.set_root(1);
+		assert!(tree.insert_child(0, 2, 5).is_some());
+		let serialized = ::serde_json::to_string(&tree).unwrap();
+		let tree: ImplicitTree<i32> = ::serde_json::from_str(&serialized).unwrap();
+		assert_eq!(tree.width(), 3);
+		assert_eq!(tree.get(&0), Some(&1));
+		assert_eq!(tree.get(&tree.child(&0, 2)), Some(&5));
+		assert_eq!(tree.get(&1), None);
+	}
 }
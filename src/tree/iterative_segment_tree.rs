@@ -0,0 +1,175 @@
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use operator::Monoid;
+use OwnedRef;
+use Vec;
+
+/// A variant of `SegmentTree` that does not recurse
+///
+/// The tree is stored in a flat array of `2n` nodes. The leaves are the
+/// last `n` nodes and every other node `i` combines the nodes `2i` and
+/// `2i + 1`. Queries and updates walk from the leaves towards the root
+/// without recursion so they are considerably faster than `SegmentTree`:
+/// - Construct: O(`n`)
+/// - Query: O(`log n`)
+/// - Update: O(`log n`)
+///
+/// The operator does not need to be commutative.
+pub struct IterativeSegmentTree<T, O> {
+	nodes: Vec<Option<T>>,
+	operator: O,
+	length: usize,
+}
+
+impl<T, O> IterativeSegmentTree<T, O> where O: Monoid<T> {
+	pub fn construct(values: impl FixedDataSource<T>, operator: O) -> IterativeSegmentTree<T, O> {
+		let length = values.len();
+		let mut nodes: Vec<Option<T>> = (0..length).map(|_| None).collect();
+		nodes.extend(values.map(Some));
+
+		for node in (1..length).rev() {
+			let value = operator.operate(nodes[2 * node].as_ref().unwrap(), nodes[2 * node + 1].as_ref().unwrap());
+			nodes[node] = Some(value);
+		}
+
+		IterativeSegmentTree {
+			nodes,
+			operator,
+			length,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.length
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.length == 0
+	}
+
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	/// The operator must have an identity if the range is empty
+	pub fn query(&self, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if it is empty and the operator has no identity
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		let range = ::range::bounds(range, self.length)?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		let (mut left, mut right) = (range.start + self.length, range.end + self.length);
+		let mut left_value: Option<OwnedRef<'_, T>> = None;
+		let mut right_value: Option<OwnedRef<'_, T>> = None;
+		while left < right {
+			if left & 1 == 1 {
+				let node = self.node(left);
+				left_value = Some(match left_value {
+					Some(value) => self.operator.operate(&value, node).into(),
+					None => node.into(),
+				});
+				left += 1;
+			}
+
+			if right & 1 == 1 {
+				right -= 1;
+				let node = self.node(right);
+				right_value = Some(match right_value {
+					Some(value) => self.operator.operate(node, &value).into(),
+					None => node.into(),
+				});
+			}
+
+			left >>= 1;
+			right >>= 1;
+		}
+
+		Ok(match (left_value, right_value) {
+			(Some(left_value), Some(right_value)) => self.operator.operate(&left_value, &right_value).into(),
+			(Some(value), None) | (None, Some(value)) => value,
+			(None, None) => unreachable!(),
+		})
+	}
+
+	/// Updates a value
+	///
+	/// # Panics
+	///
+	/// `index` must be less than the length of the input array
+	pub fn update(&mut self, index: usize, value: T) {
+		self.try_update(index, value).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Updates a value
+	///
+	/// # Errors
+	///
+	/// Returns an error if `index` is not less than the length of the input array
+	pub fn try_update(&mut self, index: usize, value: T) -> Result<(), Error> {
+		if index >= self.length { return Err(Error::OutOfBounds { index, length: self.length }); }
+		let mut node = index + self.length;
+		self.nodes[node] = Some(value);
+		while node > 1 {
+			node >>= 1;
+			let value = self.operator.operate(self.node(2 * node), self.node(2 * node + 1));
+			self.nodes[node] = Some(value);
+		}
+		Ok(())
+	}
+
+	fn node(&self, node: usize) -> &T {
+		self.nodes[node].as_ref().unwrap()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_summation() {
+		use core::ops::Deref;
+		let mut data = vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+		let mut tree = IterativeSegmentTree::construct(data.clone().into_iter(), ::operator::summation_default());
+		assert_eq!(*tree.query(3..3).deref(), 0);
+
+		for &(index, value) in [(0, 0), (10, 7), (4, -3), (6, 2)].iter() {
+			tree.update(index, value);
+			data[index] = value;
+			for right in 0..data.len() {
+				for left in 0..=right {
+					assert_eq!(*tree.query(left..=right).deref(), data[left..=right].iter().sum::<i32>());
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_non_commutative() {
+		use core::ops::Deref;
+		// Composition of affine maps `x -> a * x + b` depends on order
+		let data: Vec<(i64, i64)> = (1..=9).map(|value| (value % 3 + 1, value)).collect();
+		let operator = |first: &(i64, i64), second: &(i64, i64)| (first.0 * second.0, second.0 * first.1 + second.1);
+		let tree = IterativeSegmentTree::construct(data.clone().into_iter(), operator);
+		for right in 0..data.len() {
+			for left in 0..=right {
+				let expected = data[left + 1..=right].iter().fold(data[left], |value, next| operator(&value, next));
+				assert_eq!(*tree.query(left..=right).deref(), expected);
+			}
+		}
+	}
+}
@@ -1,31 +1,46 @@
 use core::mem::replace;
 use core::ops::Deref;
 use core::ops::DerefMut;
+use core::ops::RangeBounds;
+use Error;
 use FixedDataSource;
-use operator::AssociativeOperator;
+use operator::Monoid;
 use OwnedRef;
 use super::BackingTree;
+use super::DeltaComposer;
 use super::DeltaSifter;
 use super::DeltaWrapper;
 use super::ImplicitTree;
+use Vec;
 
 /// A variant of `SegmentTree` that allows range updates
 ///
 /// `LazySegmentTree` allows range queries to be pending and only
 /// executed when needed. The cost is then added to the query operation
 /// if an update is pending.
-pub struct LazySegmentTree<T, B, O, S, D> where B: BackingTree {
+///
+/// Values can be appended and removed from the end in the same way as
+/// `SegmentTree`.
+///
+/// By default a pending delta is sifted down before a newer delta replaces it.
+/// If deltas can be merged with a `DeltaComposer` then the tree should be
+/// constructed with `construct_composed` so that pending deltas are merged in
+/// place instead.
+pub struct LazySegmentTree<T, B, O, S, D, C = fn(&D, &D) -> D> where B: BackingTree {
 	tree: B,
 	operator: O,
 	length: usize,
+	capacity: usize,
+	width: usize,
 	sifter: S,
+	composer: Option<C>,
 	_type: ::core::marker::PhantomData<T>,
 	_delta_type: ::core::marker::PhantomData<D>,
 }
 
-impl<T, B, O, S, D> LazySegmentTree<T, B, O, S, D>
-	where O: AssociativeOperator<T>, B: BackingTree<Value=DeltaWrapper<T, D>>,
-	      S: DeltaSifter<T, D>, D: Clone {
+impl<T, B, O, S, D, C> LazySegmentTree<T, B, O, S, D, C>
+	where O: Monoid<T>, B: BackingTree<Value=DeltaWrapper<T, D>>,
+	      S: DeltaSifter<T, D>, D: Clone, C: DeltaComposer<D> {
 	fn construct_recursively(values: &mut impl FixedDataSource<DeltaWrapper<T, D>>, operator: &mut O,
 	                         tree: &mut ImplicitTree<DeltaWrapper<T, D>>, range_left: usize,
 	                         range_right: usize, node: usize) {
@@ -35,60 +50,202 @@ impl<T, B, O, S, D> LazySegmentTree<T, B, O, S, D>
 			return;
 		}
 
-		let (middle_left, middle_right) = super::functions::split_range(range_left, range_right);
-		let left_child = tree.child(&node, 0);
-		let right_child = tree.child(&node, 1);
-		Self::construct_recursively(values, operator, tree, middle_right, range_right, right_child);
-		Self::construct_recursively(values, operator, tree, range_left, middle_left, left_child);
-		let value = operator(&tree.get(&left_child).unwrap(), &tree.get(&right_child).unwrap());
+		let width = tree.width();
+		let ranges = super::functions::split_range_width(range_left, range_right, width);
+		for (index, (child_left, child_right)) in ranges.enumerate().rev() {
+			let child = tree.child(&node, index);
+			Self::construct_recursively(values, operator, tree, child_left, child_right, child);
+		}
+
+		let value = combine_children(operator, tree, &node, width, range_left, range_right);
 		unsafe { tree.insert_unchecked(node, DeltaWrapper::new(value)) };
 	}
 
-	pub fn query(&self, left: usize, right: usize) -> OwnedRef<T> {
-		assert!(left <= right && right < self.length);
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	/// The operator must have an identity if the range is empty
+	pub fn query(&self, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if it is empty and the operator has no identity
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		let range = ::range::bounds(range, self.length)?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
 		let root = self.tree.root();
-		let last = self.length - 1;
-		self.query_recursively(root, left, right, 0, last).unwrap()
+		let last = self.capacity - 1;
+		Ok(self.query_recursively(root, range.start, range.end - 1, 0, last).unwrap())
 	}
 
 	fn query_recursively(&self, node: B::Identifier, left: usize, right: usize,
 	                     current_left: usize, current_right: usize) -> Option<OwnedRef<T>> {
+		if right < current_left || current_right < left { return None; }
 		self.sift_node(&node, current_left, current_right);
 
 		// Same as SegmentTree query_recursively
 		let in_range = left <= current_left && current_right <= right;
-		if in_range { return self.tree.get(&node).and_then(|reference| Some(reference.deref().into())); }
+		if in_range { return self.tree.get(&node).map(|reference| reference.deref().into()); }
 
-		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
-		let mut left_value = None;
-		let mut right_value = None;
-		if middle_left >= left {
-			let left_child = self.tree.child(&node, 0);
-			left_value = self.query_recursively(left_child, left, right, current_left, middle_left);
+		let mut value: Option<OwnedRef<T>> = None;
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			let child = self.tree.child(&node, index);
+			value = match (value, self.query_recursively(child, left, right, child_left, child_right)) {
+				(Some(value), Some(child_value)) => Some(self.operator.operate(&value, &child_value).into()),
+				(value, None) => value,
+				(None, child_value) => child_value,
+			};
 		}
-		if middle_right <= right {
-			let right_child = self.tree.child(&node, 1);
-			right_value = self.query_recursively(right_child, left, right, middle_right, current_right);
+		value
+	}
+
+	/// Finds the furthest `right` such that `predicate` holds for the range `left..=right`
+	///
+	/// See `SegmentTree::max_right` for the requirements on `predicate`.
+	/// Pending updates are sifted down along the descent.
+	///
+	/// # Errors
+	///
+	/// Returns `None` if `predicate` does not hold for the range `left..=left`
+	///
+	/// # Panics
+	///
+	/// `left` must be less than the length of the input array
+	pub fn max_right<F>(&self, left: usize, predicate: F) -> Option<usize> where F: Fn(&T) -> bool {
+		assert!(left < self.length);
+		let mut accumulator = None;
+		let root = self.tree.root();
+		match self.max_right_recursively(root, left, 0, self.capacity - 1, &mut accumulator, &predicate) {
+			Some(boundary) if boundary == left => None,
+			Some(boundary) => Some(boundary - 1),
+			None => Some(self.length - 1),
 		}
+	}
 
-		if let Some(left_value) = left_value {
-			if let Some(right_value) = right_value {
-				Some((self.operator)(&left_value, &right_value).into())
-			} else {
-				Some(left_value)
+	fn max_right_recursively<'a, F>(&'a self, node: B::Identifier, left: usize, current_left: usize,
+	                                current_right: usize, accumulator: &mut Option<OwnedRef<'a, T>>,
+	                                predicate: &F) -> Option<usize> where F: Fn(&T) -> bool {
+		if current_right < left { return None; }
+		self.sift_node(&node, current_left, current_right);
+
+		// Same as SegmentTree max_right_recursively
+		if left <= current_left {
+			let value = self.tree.get(&node).unwrap().deref();
+			let candidate: OwnedRef<T> = match accumulator {
+				Some(accumulator) => self.operator.operate(accumulator, value).into(),
+				None => value.into(),
+			};
+
+			if predicate(&candidate) {
+				*accumulator = Some(candidate);
+				return None;
+			} else if current_left == current_right {
+				return Some(current_left);
 			}
-		} else if let Some(right_value) = right_value {
-			Some(right_value.into())
-		} else {
-			unreachable!()
 		}
+
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			let child = self.tree.child(&node, index);
+			let boundary = self.max_right_recursively(child, left, child_left, child_right, accumulator, predicate);
+			if boundary.is_some() { return boundary; }
+		}
+		None
 	}
 
-	pub fn update_range(&mut self, left: usize, right: usize, delta: &D) {
-		assert!(left <= right && right < self.length);
+	/// Finds the nearest `left` such that `predicate` holds for the range `left..=right`
+	///
+	/// This is the mirror of `max_right` and has the same requirements on `predicate`.
+	///
+	/// # Errors
+	///
+	/// Returns `None` if `predicate` does not hold for the range `right..=right`
+	///
+	/// # Panics
+	///
+	/// `right` must be less than the length of the input array
+	pub fn min_left<F>(&self, right: usize, predicate: F) -> Option<usize> where F: Fn(&T) -> bool {
+		assert!(right < self.length);
+		let mut accumulator = None;
 		let root = self.tree.root();
-		let last = self.length - 1;
-		self.update_range_recursively(root, left, right, 0, last, delta);
+		match self.min_left_recursively(root, right, 0, self.capacity - 1, &mut accumulator, &predicate) {
+			Some(boundary) if boundary == right => None,
+			Some(boundary) => Some(boundary + 1),
+			None => Some(0),
+		}
+	}
+
+	fn min_left_recursively<'a, F>(&'a self, node: B::Identifier, right: usize, current_left: usize,
+	                               current_right: usize, accumulator: &mut Option<OwnedRef<'a, T>>,
+	                               predicate: &F) -> Option<usize> where F: Fn(&T) -> bool {
+		if current_left > right { return None; }
+		self.sift_node(&node, current_left, current_right);
+
+		// Same as SegmentTree min_left_recursively
+		if current_right <= right {
+			let value = self.tree.get(&node).unwrap().deref();
+			let candidate: OwnedRef<T> = match accumulator {
+				Some(accumulator) => self.operator.operate(value, accumulator).into(),
+				None => value.into(),
+			};
+
+			if predicate(&candidate) {
+				*accumulator = Some(candidate);
+				return None;
+			} else if current_left == current_right {
+				return Some(current_left);
+			}
+		}
+
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate().rev() {
+			let child = self.tree.child(&node, index);
+			let boundary = self.min_left_recursively(child, right, child_left, child_right, accumulator, predicate);
+			if boundary.is_some() { return boundary; }
+		}
+		None
+	}
+
+	fn identity(&self) -> T {
+		self.operator.identity().expect("operator has no identity for an empty range")
+	}
+
+	/// Applies `delta` to every value in a range
+	///
+	/// Nothing is updated if the range is empty.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn update_range(&mut self, range: impl RangeBounds<usize>, delta: &D) {
+		self.try_update_range(range, delta).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Applies `delta` to every value in a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_update_range(&mut self, range: impl RangeBounds<usize>, delta: &D) -> Result<(), Error> {
+		let range = ::range::bounds(range, self.length)?;
+		if range.is_empty() { return Ok(()); }
+		let root = self.tree.root();
+		let last = self.capacity - 1;
+		self.update_range_recursively(root, range.start, range.end - 1, 0, last, delta);
+		Ok(())
 	}
 
 	fn update_range_recursively(&mut self, node: B::Identifier, left: usize, right: usize,
@@ -102,22 +259,77 @@ impl<T, B, O, S, D> LazySegmentTree<T, B, O, S, D>
 			return;
 		}
 
-		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
-		if middle_left >= left {
-			let left_child = self.tree.child(&node, 0);
-			self.update_range_recursively(left_child, left, right, current_left, middle_left, delta);
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			if right < child_left || child_right < left { continue; }
+			let child = self.tree.child(&node, index);
+			self.update_range_recursively(child, left, right, child_left, child_right, delta);
 		}
-		if middle_right <= right {
-			let right_child = self.tree.child(&node, 1);
-			self.update_range_recursively(right_child, left, right, middle_right, current_right, delta);
+		self.pull_node(&node, current_left, current_right);
+	}
+
+	/// Replaces a value and returns the previous value
+	fn update_recursively(&mut self, node: B::Identifier, index: usize, value: T,
+	                      current_left: usize, current_right: usize) -> T {
+		self.sift_node(&node, current_left, current_right);
+		if current_left == current_right {
+			return replace(self.tree.get_mut(&node).unwrap().deref_mut(), value);
 		}
 
-		let value = {
-			let left_child = self.tree.get(&self.tree.child(&node, 0)).unwrap();
-			let right_child = self.tree.get(&self.tree.child(&node, 1)).unwrap();
-			(self.operator)(left_child, right_child)
-		};
-		replace(self.tree.get_mut(&node).unwrap().deref_mut(), value);
+		let mut ranges = super::functions::split_range_width(current_left, current_right, self.width).enumerate();
+		let (child_index, (child_left, child_right)) = ranges.find(|(_, (_, child_right))| index <= *child_right).unwrap();
+		let child = self.tree.child(&node, child_index);
+		let previous = self.update_recursively(child, index, value, child_left, child_right);
+		self.pull_node(&node, current_left, current_right);
+		previous
+	}
+
+	/// Recomputes the value of a node from its children
+	///
+	/// The children are sifted first as their values do not include their pending deltas.
+	fn pull_node(&mut self, node: &B::Identifier, current_left: usize, current_right: usize) {
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			self.sift_node(&self.tree.child(node, index), child_left, child_right);
+		}
+
+		let value = combine_children(&self.operator, &self.tree, node, self.width, current_left, current_right);
+		*self.tree.get_mut(node).unwrap().deref_mut() = value;
+	}
+
+	pub fn len(&self) -> usize {
+		self.length
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.length == 0
+	}
+
+	/// Iterates over the values in order
+	///
+	/// Every pending delta is sifted down to the leaves first.
+	pub fn iter(&self) -> impl Iterator<Item=&T> {
+		let mut leaves = Vec::with_capacity(self.length);
+		if self.length > 0 {
+			self.leaves(self.tree.root(), 0, self.capacity - 1, &mut leaves);
+		}
+		leaves.into_iter().map(move |node| self.tree.get(&node).unwrap().deref())
+	}
+
+	/// Collects the leaves that hold values in order
+	fn leaves(&self, node: B::Identifier, current_left: usize, current_right: usize,
+	          leaves: &mut Vec<B::Identifier>) {
+		if current_left >= self.length { return; }
+		self.sift_node(&node, current_left, current_right);
+		if current_left == current_right {
+			leaves.push(node);
+			return;
+		}
+
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			self.leaves(self.tree.child(&node, index), child_left, child_right, leaves);
+		}
 	}
 
 	fn sift_node(&self, node: &B::Identifier, current_left: usize, current_right: usize) {
@@ -136,41 +348,230 @@ impl<T, B, O, S, D> LazySegmentTree<T, B, O, S, D>
 		}
 		if range_length == 1 { return; }
 
-		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
-		let left_child_node = self.tree.child(node, 0);
-		if let Some(left_child) = self.tree.get(&left_child_node) {
-			self.sift_node(&left_child_node, current_left, middle_left);
-			left_child.delta.replace(Some(node_delta.clone()));
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			self.push_delta(&self.tree.child(node, index), child_left, child_right, &node_delta);
 		}
-		let right_child_node = self.tree.child(node, 1);
-		if let Some(right_child) = self.tree.get(&right_child_node) {
-			self.sift_node(&right_child_node, middle_right, current_right);
-			right_child.delta.replace(Some(node_delta.clone()));
+	}
+
+	fn push_delta(&self, node: &B::Identifier, current_left: usize, current_right: usize, delta: &D) {
+		let current_node = match self.tree.get(node) {
+			Some(current_node) => current_node,
+			None => return,
+		};
+
+		if let Some(ref composer) = self.composer {
+			let mut pending = current_node.delta.borrow_mut();
+			let composed = match *pending {
+				Some(ref pending) => composer(pending, delta),
+				None => delta.clone(),
+			};
+			*pending = Some(composed);
+		} else {
+			self.sift_node(node, current_left, current_right);
+			current_node.delta.replace(Some(delta.clone()));
 		}
 	}
 }
 
 impl<T, O, S, D> LazySegmentTree<T, ImplicitTree<DeltaWrapper<T, D>>, O, S, D>
-	where O: AssociativeOperator<T>, S: DeltaSifter<T, D>, D: Clone {
-	pub fn construct_implicit(values: impl FixedDataSource<T>, mut operator: O, sifter: S)
+	where O: Monoid<T>, S: DeltaSifter<T, D>, D: Clone {
+	pub fn construct_implicit(values: impl FixedDataSource<T>, operator: O, sifter: S)
 	                          -> LazySegmentTree<T, ImplicitTree<DeltaWrapper<T, D>>, O, S, D> {
-		let mut tree = ImplicitTree::new(super::BINARY_WIDTH);
+		Self::construct_with_width(values, operator, sifter, super::BINARY_WIDTH)
+	}
+
+	/// Constructs a tree where every node has up to `width` children
+	///
+	/// See `SegmentTree::construct_with_width` for the tradeoffs.
+	///
+	/// # Panics
+	///
+	/// `width` must be at least two
+	pub fn construct_with_width(values: impl FixedDataSource<T>, mut operator: O, sifter: S, width: usize)
+	                            -> LazySegmentTree<T, ImplicitTree<DeltaWrapper<T, D>>, O, S, D> {
+		assert!(width >= 2);
+		let mut tree = ImplicitTree::new(width);
 		let mut values = values.map(|value| DeltaWrapper::new(value));
 		let root = tree.root();
 		let length = values.len();
-		Self::construct_recursively(&mut values, &mut operator, &mut tree, 0, length - 1, root);
+		if length > 0 {
+			Self::construct_recursively(&mut values, &mut operator, &mut tree, 0, length - 1, root);
+		}
+
 		LazySegmentTree {
 			tree,
 			operator,
 			length,
+			capacity: length,
+			width,
 			sifter,
+			composer: None,
 			_type: Default::default(),
 			_delta_type: Default::default(),
 		}
 	}
 }
 
-impl<T, B, O, S, D> ::core::fmt::Debug for LazySegmentTree<T, B, O, S, D>
+impl<T, O, S, D, C> LazySegmentTree<T, ImplicitTree<DeltaWrapper<T, D>>, O, S, D, C>
+	where O: Monoid<T>, S: DeltaSifter<T, D>, D: Clone, C: DeltaComposer<D> {
+	/// Constructs a tree that merges pending deltas with `composer`
+	///
+	/// Updates no longer need to sift pending deltas further down the
+	/// tree so range updates take O(`log n`).
+	pub fn construct_composed(values: impl FixedDataSource<T>, operator: O, sifter: S, composer: C)
+	                          -> LazySegmentTree<T, ImplicitTree<DeltaWrapper<T, D>>, O, S, D, C> {
+		let tree = LazySegmentTree::construct_implicit(values, operator, sifter);
+		LazySegmentTree {
+			tree: tree.tree,
+			operator: tree.operator,
+			length: tree.length,
+			capacity: tree.capacity,
+			width: tree.width,
+			sifter: tree.sifter,
+			composer: Some(composer),
+			_type: Default::default(),
+			_delta_type: Default::default(),
+		}
+	}
+
+	/// Appends a value to the end
+	///
+	/// See `SegmentTree::push` for the cost of growing the tree.
+	///
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn push(&mut self, value: T) {
+		if self.length == self.capacity {
+			let capacity = ::core::cmp::max(1, self.capacity * 2);
+			self.resize(capacity);
+		}
+
+		self.length += 1;
+		let root = self.tree.root();
+		let (index, last) = (self.length - 1, self.capacity - 1);
+		self.update_recursively(root, index, value, 0, last);
+	}
+
+	/// Removes the last value and returns it
+	///
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn pop(&mut self) -> Option<T> {
+		if self.length == 0 { return None; }
+		let identity = self.identity();
+		let root = self.tree.root();
+		let (index, last) = (self.length - 1, self.capacity - 1);
+		let value = self.update_recursively(root, index, identity, 0, last);
+
+		self.length -= 1;
+		if self.length * 4 <= self.capacity {
+			let capacity = self.capacity / 2;
+			self.resize(capacity);
+		}
+		Some(value)
+	}
+
+	/// Appends every value to the end
+	///
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn extend<I>(&mut self, values: I) where I: IntoIterator<Item=T> {
+		values.into_iter().for_each(|value| self.push(value));
+	}
+
+	/// Converts the tree into its values in order with every pending delta applied
+	pub fn into_vec(mut self) -> Vec<T> {
+		self.take_values()
+	}
+
+	/// Same as SegmentTree resize
+	fn resize(&mut self, capacity: usize) {
+		let mut values = self.take_values();
+		super::functions::extend_until(&mut values, capacity, &mut || self.identity());
+
+		let mut tree = ImplicitTree::new(self.width);
+		if capacity > 0 {
+			let root = tree.root();
+			let mut values = values.into_iter().map(|value| DeltaWrapper::new(value));
+			Self::construct_recursively(&mut values, &mut self.operator, &mut tree, 0, capacity - 1, root);
+		}
+
+		self.tree = tree;
+		self.capacity = capacity;
+	}
+
+	fn take_values(&mut self) -> Vec<T> {
+		let mut leaves = Vec::with_capacity(self.length);
+		if self.length > 0 {
+			self.leaves(self.tree.root(), 0, self.capacity - 1, &mut leaves);
+		}
+
+		let tree = &mut self.tree;
+		leaves.into_iter().map(|node| unsafe { tree.remove_unchecked(node) }.unwrap().into_inner()).collect()
+	}
+}
+
+/// Same as SegmentTree combine_children
+fn combine_children<T, B, O, D>(operator: &O, tree: &B, node: &B::Identifier, width: usize,
+                                current_left: usize, current_right: usize) -> T
+	where O: Monoid<T>, B: BackingTree<Value=DeltaWrapper<T, D>> {
+	let count = super::functions::split_range_width(current_left, current_right, width).len();
+	let mut children = (0..count).map(|index| tree.get(&tree.child(node, index)).unwrap().deref());
+	let (first, second) = (children.next().unwrap(), children.next().unwrap());
+	children.fold(operator.operate(first, second), |value, child| operator.operate(&value, child))
+}
+
+/// Pending deltas are serialized but the operator, sifter and composer
+/// must be given again with `deserialize` or `deserialize_composed`
+#[cfg(feature = "serde")]
+impl<T, B, O, S, D, C> ::serde::Serialize for LazySegmentTree<T, B, O, S, D, C>
+	where B: BackingTree + ::serde::Serialize {
+	fn serialize<E>(&self, serializer: E) -> Result<E::Ok, E::Error> where E: ::serde::Serializer {
+		(&self.tree, self.length, self.capacity, self.width).serialize(serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<T, B, O, S, D> LazySegmentTree<T, B, O, S, D> where B: BackingTree {
+	/// Deserializes a tree with the operator and sifter it was constructed with
+	pub fn deserialize<'de, E>(deserializer: E, operator: O, sifter: S) -> Result<Self, E::Error>
+		where E: ::serde::Deserializer<'de>, B: ::serde::Deserialize<'de> {
+		LazySegmentTree::deserialize_parts(deserializer, operator, sifter, None)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<T, B, O, S, D, C> LazySegmentTree<T, B, O, S, D, C> where B: BackingTree {
+	/// Deserializes a tree that merges pending deltas with `composer`
+	pub fn deserialize_composed<'de, E>(deserializer: E, operator: O, sifter: S, composer: C)
+	                                    -> Result<Self, E::Error>
+		where E: ::serde::Deserializer<'de>, B: ::serde::Deserialize<'de> {
+		Self::deserialize_parts(deserializer, operator, sifter, Some(composer))
+	}
+
+	fn deserialize_parts<'de, E>(deserializer: E, operator: O, sifter: S, composer: Option<C>)
+	                             -> Result<Self, E::Error>
+		where E: ::serde::Deserializer<'de>, B: ::serde::Deserialize<'de> {
+		let (tree, length, capacity, width) = ::serde::Deserialize::deserialize(deserializer)?;
+		Ok(LazySegmentTree {
+			tree,
+			operator,
+			length,
+			capacity,
+			width,
+			sifter,
+			composer,
+			_type: Default::default(),
+			_delta_type: Default::default(),
+		})
+	}
+}
+
+impl<T, B, O, S, D, C> ::core::fmt::Debug for LazySegmentTree<T, B, O, S, D, C>
 	where B: ::core::fmt::Debug + BackingTree {
 	fn fmt(&self, f: &mut ::core::fmt::Formatter) -> Result<(), ::core::fmt::Error> {
 		writeln!(f, "{:?}", self.tree)
@@ -187,23 +588,23 @@ mod tests {
 		let data = vec![1, 2, 3, 4, 5, 6, 7];
 		let sifter = |current: &i32, delta: &i32, length: usize| *current + (*delta * length as i32);
 		let mut tree = LazySegmentTree::construct_implicit(data.into_iter(), ::operator::summation(), sifter);
-		assert_eq!(*tree.query(0, 3).deref(), 1 + 2 + 3 + 4);
-		assert_eq!(*tree.query(0, 6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 7);
-		assert_eq!(*tree.query(4, 6).deref(), 5 + 6 + 7);
-		assert_eq!(*tree.query(0, 0).deref(), 1);
-		assert_eq!(*tree.query(2, 3).deref(), 3 + 4);
-		tree.update_range(0, 6, &5);
-		assert_eq!(*tree.query(6, 6).deref(), 12);
-		assert_eq!(*tree.query(4, 6).deref(), 10 + 11 + 12);
-		assert_eq!(*tree.query(0, 6).deref(), 6 + 7 + 8 + 9 + 10 + 11 + 12);
-		assert_eq!(*tree.query(2, 3).deref(), 8 + 9);
-		tree.update_range(0, 6, &-2);
-		tree.update_range(0, 6, &-3);
-		assert_eq!(*tree.query(0, 3).deref(), 1 + 2 + 3 + 4);
-		assert_eq!(*tree.query(0, 6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 7);
-		assert_eq!(*tree.query(4, 6).deref(), 5 + 6 + 7);
-		assert_eq!(*tree.query(0, 0).deref(), 1);
-		assert_eq!(*tree.query(2, 3).deref(), 3 + 4);
+		assert_eq!(*tree.query(0..=3).deref(), 1 + 2 + 3 + 4);
+		assert_eq!(*tree.query(0..=6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 7);
+		assert_eq!(*tree.query(4..=6).deref(), 5 + 6 + 7);
+		assert_eq!(*tree.query(0..=0).deref(), 1);
+		assert_eq!(*tree.query(2..=3).deref(), 3 + 4);
+		tree.update_range(0..=6, &5);
+		assert_eq!(*tree.query(6..=6).deref(), 12);
+		assert_eq!(*tree.query(4..=6).deref(), 10 + 11 + 12);
+		assert_eq!(*tree.query(0..=6).deref(), 6 + 7 + 8 + 9 + 10 + 11 + 12);
+		assert_eq!(*tree.query(2..=3).deref(), 8 + 9);
+		tree.update_range(0..=6, &-2);
+		tree.update_range(0..=6, &-3);
+		assert_eq!(*tree.query(0..=3).deref(), 1 + 2 + 3 + 4);
+		assert_eq!(*tree.query(0..=6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 7);
+		assert_eq!(*tree.query(4..=6).deref(), 5 + 6 + 7);
+		assert_eq!(*tree.query(0..=0).deref(), 1);
+		assert_eq!(*tree.query(2..=3).deref(), 3 + 4);
 	}
 
 	#[test]
@@ -212,19 +613,155 @@ mod tests {
 		let data = vec![1, 2, 3, 4, 5, 6, 7];
 		let sifter = |_: &u32, delta: &u32, _| *delta;
 		let mut tree = LazySegmentTree::construct_implicit(data.into_iter(), ::operator::minimum(), sifter);
-		assert_eq!(*tree.query(0, 3).deref(), 1);
-		assert_eq!(*tree.query(0, 6).deref(), 1);
-		assert_eq!(*tree.query(4, 6).deref(), 5);
-		assert_eq!(*tree.query(0, 0).deref(), 1);
-		assert_eq!(*tree.query(2, 3).deref(), 3);
-		tree.update_range(6, 6, &0);
-		assert_eq!(*tree.query(6, 6).deref(), 0);
-		assert_eq!(*tree.query(4, 6).deref(), 0);
-		assert_eq!(*tree.query(0, 5).deref(), 1);
-		tree.update_range(4, 5, &1000);
-		assert_eq!(*tree.query(6, 6).deref(), 0);
-		assert_eq!(*tree.query(0, 3).deref(), 1);
-		assert_eq!(*tree.query(0, 5).deref(), 1);
-		assert_eq!(*tree.query(5, 5).deref(), 1000);
+		assert_eq!(*tree.query(0..=3).deref(), 1);
+		assert_eq!(*tree.query(0..=6).deref(), 1);
+		assert_eq!(*tree.query(4..=6).deref(), 5);
+		assert_eq!(*tree.query(0..=0).deref(), 1);
+		assert_eq!(*tree.query(2..=3).deref(), 3);
+		tree.update_range(6..=6, &0);
+		assert_eq!(*tree.query(6..=6).deref(), 0);
+		assert_eq!(*tree.query(4..=6).deref(), 0);
+		assert_eq!(*tree.query(0..=5).deref(), 1);
+		tree.update_range(4..=5, &1000);
+		assert_eq!(*tree.query(6..=6).deref(), 0);
+		assert_eq!(*tree.query(0..=3).deref(), 1);
+		assert_eq!(*tree.query(0..=5).deref(), 1);
+		assert_eq!(*tree.query(5..=5).deref(), 1000);
+	}
+
+	#[test]
+	fn test_binary_search() {
+		let mut data = vec![3, 1, 4, 1, 5, 9, 2, 6];
+		let sifter = |current: &i32, delta: &i32, length: usize| *current + (*delta * length as i32);
+		let mut tree = LazySegmentTree::construct_implicit(data.clone().into_iter(), ::operator::summation(), sifter);
+		tree.update_range(2..=5, &2);
+		data[2..=5].iter_mut().for_each(|value| *value += 2);
+
+		for left in 0..data.len() {
+			for limit in 0..48 {
+				let expected = (left..data.len()).take_while(|right| {
+					data[left..=*right].iter().sum::<i32>() <= limit
+				}).last();
+				assert_eq!(tree.max_right(left, |sum| *sum <= limit), expected);
+			}
+		}
+
+		for right in 0..data.len() {
+			for limit in 0..48 {
+				let expected = (0..=right).rev().take_while(|left| {
+					data[*left..=right].iter().sum::<i32>() <= limit
+				}).last();
+				assert_eq!(tree.min_left(right, |sum| *sum <= limit), expected);
+			}
+		}
+	}
+
+	#[test]
+	fn test_composed() {
+		use core::ops::Deref;
+		// Deltas are affine maps `x -> a * x + b`
+		let mut data = vec![1i64, 2, 3, 4, 5, 6, 7, 8, 9];
+		let sifter = |current: &i64, delta: &(i64, i64), length: usize| {
+			delta.0 * *current + delta.1 * length as i64
+		};
+		let composer = |pending: &(i64, i64), delta: &(i64, i64)| {
+			(delta.0 * pending.0, delta.0 * pending.1 + delta.1)
+		};
+		let mut tree = LazySegmentTree::construct_composed(data.clone().into_iter(),
+		                                                   ::operator::summation(), sifter, composer);
+
+		let updates = [(0, 8, (1, 3)), (2, 5, (2, 0)), (4, 7, (0, 5)), (1, 4, (3, -1)), (0, 8, (1, 1))];
+		for &(left, right, delta) in updates.iter() {
+			tree.update_range(left..=right, &delta);
+			data[left..=right].iter_mut().for_each(|value| *value = delta.0 * *value + delta.1);
+			for right in 0..data.len() {
+				for left in 0..=right {
+					assert_eq!(*tree.query(left..=right).deref(), data[left..=right].iter().sum::<i64>());
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_empty() {
+		use core::ops::Deref;
+		let sifter = |current: &i32, delta: &i32, length: usize| *current + (*delta * length as i32);
+		let mut tree = LazySegmentTree::construct_implicit(::core::iter::empty(), ::operator::summation_default(), sifter);
+		tree.update_range(.., &5);
+		assert_eq!(*tree.query(..).deref(), 0);
+
+		let mut tree = LazySegmentTree::construct_implicit(vec![1, 2, 3].into_iter(), ::operator::summation_default(), sifter);
+		tree.update_range(2..2, &5);
+		assert_eq!(*tree.query(2..2).deref(), 0);
+		assert_eq!(*tree.query(0..=2).deref(), 1 + 2 + 3);
+	}
+
+	#[test]
+	fn test_growth() {
+		use core::ops::Deref;
+		let sifter = |current: &i32, delta: &i32, length: usize| *current + (*delta * length as i32);
+		let mut tree = LazySegmentTree::construct_implicit(::core::iter::empty(), ::operator::summation_default(), sifter);
+		let mut data = Vec::new();
+		for value in 1..=9 {
+			tree.push(value);
+			data.push(value);
+			tree.update_range(.., &1);
+			tree.update_range(0..=0, &1);
+			data.iter_mut().for_each(|value| *value += 1);
+			data[0] += 1;
+			assert_eq!(tree.len(), data.len());
+			assert_eq!(*tree.query(..).deref(), data.iter().sum::<i32>());
+		}
+
+		tree.extend(vec![20, 30]);
+		data.extend(vec![20, 30]);
+		for _ in 0..6 {
+			assert_eq!(tree.pop(), data.pop());
+			for right in 0..data.len() {
+				for left in 0..=right {
+					assert_eq!(*tree.query(left..=right).deref(), data[left..=right].iter().sum::<i32>());
+				}
+			}
+		}
+
+		assert!(tree.iter().eq(data.iter()));
+		assert_eq!(tree.into_vec(), data);
+	}
+
+	#[test]
+	fn test_width() {
+		use core::ops::Deref;
+		let sifter = |current: &i32, delta: &i32, length: usize| *current + (*delta * length as i32);
+		for width in 2..=8 {
+			let mut data = vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7];
+			let mut tree = LazySegmentTree::construct_with_width(data.clone().into_iter(),
+			                                                     ::operator::summation(), sifter, width);
+			for &(left, right, delta) in [(0, 13, 1), (2, 11, -2), (5, 5, 4), (7, 13, 3)].iter() {
+				tree.update_range(left..=right, &delta);
+				data[left..=right].iter_mut().for_each(|value| *value += delta);
+				for right in 0..data.len() {
+					for left in 0..=right {
+						assert_eq!(*tree.query(left..=right).deref(), data[left..=right].iter().sum::<i32>());
+					}
+				}
+			}
+			assert_eq!(tree.into_vec(), data);
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_serde() {
+		let sifter = |current: &i32, delta: &i32, length: usize| *current + (*delta * length as i32);
+		let mut tree = LazySegmentTree::construct_implicit(vec![1, 2, 3, 4, 5].into_iter(), ::operator::summation(), sifter);
+		tree.update_range(.., &10);
+		tree.update_range(1..4, &-1);
+
+		// Pending deltas are kept in the serialized tree
+		let serialized = ::serde_json::to_string(&tree).unwrap();
+		let mut deserializer = ::serde_json::Deserializer::from_str(&serialized);
+		let tree: LazySegmentTree<i32, ImplicitTree<DeltaWrapper<i32, i32>>, _, _, i32> =
+			LazySegmentTree::deserialize(&mut deserializer, ::operator::summation(), sifter).unwrap();
+		assert_eq!(tree.into_vec(), vec![11, 11, 12, 13, 15]);
 	}
 }
@@ -0,0 +1,480 @@
+use core::mem::replace;
+use core::mem::swap;
+use FixedDataSource;
+use operator::AssociativeOperator;
+use super::DeltaComposer;
+use super::DeltaSifter;
+use Vec;
+
+struct LinkCutNode<T, D> {
+	value: T,
+	/// Absent if the node has no children as the aggregate is then the value
+	aggregate: Option<T>,
+	/// The aggregate of the subtree combined from right to left
+	reversed_aggregate: Option<T>,
+	size: usize,
+	/// Pending delta for the whole subtree including this node
+	delta: Option<D>,
+	/// Whether the whole subtree is pending a reversal
+	reversed: bool,
+	/// The parent in the splay tree or the path parent if this node is a splay root
+	parent: Option<usize>,
+	children: [Option<usize>; 2],
+}
+
+impl<T, D> LinkCutNode<T, D> {
+	fn aggregate(&self) -> &T {
+		self.aggregate.as_ref().unwrap_or(&self.value)
+	}
+
+	fn reversed_aggregate(&self) -> &T {
+		self.reversed_aggregate.as_ref().unwrap_or(&self.value)
+	}
+}
+
+/// A forest that can be linked and cut while answering path queries
+///
+/// Every vertex is identified by its index in the input values. Each tree
+/// in the forest is split into paths that are stored in splay trees so that
+/// edges can be added and removed without rebuilding anything:
+/// - Construct: O(`n`)
+/// - Link, cut and connected: O(`log n`)
+/// - Lowest common ancestor: O(`log n`)
+/// - Path query and path update: O(`log n`)
+///
+/// All bounds are amortized. Path queries combine the values in order
+/// from the first vertex to the second. Every node also keeps the aggregate
+/// of its subtree in reverse order so that rerooting a tree only has to swap
+/// the two.
+///
+/// Pending deltas are always merged with the composer. Splay trees can be
+/// arbitrarily deep so sifting an older delta through a subtree instead
+/// would not be bounded.
+pub struct LinkCutTree<T, O, S, D, C> {
+	nodes: Vec<LinkCutNode<T, D>>,
+	operator: O,
+	sifter: S,
+	composer: C,
+}
+
+impl<T, O, S, D, C> LinkCutTree<T, O, S, D, C>
+	where O: AssociativeOperator<T>, S: DeltaSifter<T, D>, D: Clone, C: DeltaComposer<D> {
+	/// Constructs a forest where every vertex is its own tree
+	pub fn construct(values: impl FixedDataSource<T>, operator: O, sifter: S, composer: C)
+	                 -> LinkCutTree<T, O, S, D, C> {
+		let nodes = values.map(|value| LinkCutNode {
+			value,
+			aggregate: None,
+			reversed_aggregate: None,
+			size: 1,
+			delta: None,
+			reversed: false,
+			parent: None,
+			children: [None, None],
+		}).collect();
+
+		LinkCutTree {
+			nodes,
+			operator,
+			sifter,
+			composer,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// Adds an edge between two vertices
+	///
+	/// Returns false if the vertices are already connected.
+	///
+	/// # Panics
+	///
+	/// Both vertices must be within the forest
+	pub fn link(&mut self, a: usize, b: usize) -> bool {
+		if self.connected(a, b) { return false; }
+		self.make_root(a);
+		self.nodes[a].parent = Some(b);
+		true
+	}
+
+	/// Removes the edge between two vertices
+	///
+	/// Returns false if there is no edge between the vertices.
+	///
+	/// # Panics
+	///
+	/// Both vertices must be within the forest
+	pub fn cut(&mut self, a: usize, b: usize) -> bool {
+		if a == b || !self.connected(a, b) { return false; }
+		self.make_root(a);
+		self.access(b);
+		self.sift_node(a);
+
+		// The vertices are adjacent only if nothing lies between them on the path
+		let adjacent = self.nodes[b].children[0] == Some(a) && self.nodes[a].children[1].is_none();
+		if adjacent {
+			self.nodes[b].children[0] = None;
+			self.nodes[a].parent = None;
+			self.pull_node(b);
+		}
+		adjacent
+	}
+
+	/// Checks whether two vertices are in the same tree
+	///
+	/// # Panics
+	///
+	/// Both vertices must be within the forest
+	pub fn connected(&mut self, a: usize, b: usize) -> bool {
+		a == b || self.find_root(a) == self.find_root(b)
+	}
+
+	/// Finds the lowest common ancestor of two vertices when the tree is rooted at `root`
+	///
+	/// Returns `None` if the vertices are not all in the same tree.
+	///
+	/// # Panics
+	///
+	/// Every vertex must be within the forest
+	pub fn lca(&mut self, root: usize, a: usize, b: usize) -> Option<usize> {
+		if !self.connected(root, a) || !self.connected(root, b) { return None; }
+		self.make_root(root);
+		self.access(a);
+		Some(self.access(b))
+	}
+
+	/// Combines the values on the path between two vertices
+	///
+	/// Returns `None` if the vertices are not connected.
+	///
+	/// # Panics
+	///
+	/// Both vertices must be within the forest
+	pub fn query(&mut self, a: usize, b: usize) -> Option<&T> {
+		if !self.connected(a, b) { return None; }
+		self.expose_path(a, b);
+		Some(self.nodes[b].aggregate())
+	}
+
+	/// Applies `delta` to every value on the path between two vertices
+	///
+	/// Returns false if the vertices are not connected.
+	///
+	/// # Panics
+	///
+	/// Both vertices must be within the forest
+	pub fn update_path(&mut self, a: usize, b: usize, delta: &D) -> bool {
+		if !self.connected(a, b) { return false; }
+		self.expose_path(a, b);
+		self.push_delta(b, delta);
+		true
+	}
+
+	/// Replaces the value of a vertex and returns the previous value
+	///
+	/// # Panics
+	///
+	/// `node` must be within the forest
+	pub fn update(&mut self, node: usize, value: T) -> T {
+		self.access(node);
+		let previous = replace(&mut self.nodes[node].value, value);
+		self.pull_node(node);
+		previous
+	}
+
+	/// Makes `b` the root of a splay tree holding exactly the path from `a` to `b`
+	fn expose_path(&mut self, a: usize, b: usize) {
+		self.make_root(a);
+		self.access(b);
+	}
+
+	/// Makes `node` the root of its tree
+	fn make_root(&mut self, node: usize) {
+		self.access(node);
+		self.nodes[node].reversed ^= true;
+		self.sift_node(node);
+	}
+
+	fn find_root(&mut self, node: usize) -> usize {
+		self.access(node);
+		let mut current = node;
+		loop {
+			self.sift_node(current);
+			match self.nodes[current].children[0] {
+				Some(child) => current = child,
+				None => break,
+			}
+		}
+
+		self.splay(current);
+		current
+	}
+
+	/// Moves `node` onto the path from the root and splays it
+	///
+	/// Returns the last vertex where the path from `node` joined the root path.
+	fn access(&mut self, node: usize) -> usize {
+		let mut last = None;
+		let mut current = Some(node);
+		while let Some(vertex) = current {
+			self.splay(vertex);
+			self.nodes[vertex].children[1] = last;
+			self.pull_node(vertex);
+			last = Some(vertex);
+			current = self.nodes[vertex].parent;
+		}
+
+		self.splay(node);
+		last.unwrap()
+	}
+
+	fn is_splay_root(&self, node: usize) -> bool {
+		match self.nodes[node].parent {
+			Some(parent) => !self.nodes[parent].children.contains(&Some(node)),
+			None => true,
+		}
+	}
+
+	/// Returns whether `node` is the right child of its parent
+	fn direction(&self, node: usize) -> usize {
+		let parent = self.nodes[node].parent.unwrap();
+		(self.nodes[parent].children[1] == Some(node)) as usize
+	}
+
+	fn splay(&mut self, node: usize) {
+		// Pending tags above the node must be applied before rotating
+		let mut path = vec![node];
+		let mut current = node;
+		while !self.is_splay_root(current) {
+			current = self.nodes[current].parent.unwrap();
+			path.push(current);
+		}
+		path.into_iter().rev().for_each(|vertex| self.sift_node(vertex));
+
+		while !self.is_splay_root(node) {
+			let parent = self.nodes[node].parent.unwrap();
+			if !self.is_splay_root(parent) {
+				let zig_zig = self.direction(node) == self.direction(parent);
+				self.rotate(if zig_zig { parent } else { node });
+			}
+			self.rotate(node);
+		}
+	}
+
+	/// Rotates `node` above its parent
+	fn rotate(&mut self, node: usize) {
+		let parent = self.nodes[node].parent.unwrap();
+		let direction = self.direction(node);
+		if !self.is_splay_root(parent) {
+			let grandparent = self.nodes[parent].parent.unwrap();
+			let parent_direction = self.direction(parent);
+			self.nodes[grandparent].children[parent_direction] = Some(node);
+		}
+		self.nodes[node].parent = self.nodes[parent].parent;
+
+		let inner = self.nodes[node].children[1 - direction];
+		self.nodes[parent].children[direction] = inner;
+		if let Some(inner) = inner {
+			self.nodes[inner].parent = Some(parent);
+		}
+
+		self.nodes[node].children[1 - direction] = Some(parent);
+		self.nodes[parent].parent = Some(node);
+		self.pull_node(parent);
+		self.pull_node(node);
+	}
+
+	/// Applies the pending tags of a node and passes them to its children
+	fn sift_node(&mut self, node: usize) {
+		if let Some(delta) = self.nodes[node].delta.take() {
+			let current = &mut self.nodes[node];
+			current.value = (self.sifter)(&current.value, &delta, 1);
+			if let Some(ref mut aggregate) = current.aggregate {
+				*aggregate = (self.sifter)(aggregate, &delta, current.size);
+			}
+			if let Some(ref mut aggregate) = current.reversed_aggregate {
+				*aggregate = (self.sifter)(aggregate, &delta, current.size);
+			}
+
+			let children = self.nodes[node].children;
+			for &child in children.iter().flatten() {
+				self.push_delta(child, &delta);
+			}
+		}
+
+		if self.nodes[node].reversed {
+			let current = &mut self.nodes[node];
+			current.reversed = false;
+			current.children.swap(0, 1);
+			swap(&mut current.aggregate, &mut current.reversed_aggregate);
+			let children = self.nodes[node].children;
+			for &child in children.iter().flatten() {
+				self.nodes[child].reversed ^= true;
+			}
+		}
+	}
+
+	fn push_delta(&mut self, node: usize, delta: &D) {
+		let current = &mut self.nodes[node];
+		current.delta = Some(match current.delta {
+			Some(ref pending) => (self.composer)(pending, delta),
+			None => delta.clone(),
+		});
+	}
+
+	/// Recomputes the size and aggregates of a node from its children
+	///
+	/// The children are sifted first as their aggregates do not include their pending tags.
+	fn pull_node(&mut self, node: usize) {
+		let [left, right] = self.nodes[node].children;
+		left.into_iter().chain(right).for_each(|child| self.sift_node(child));
+
+		let nodes = &self.nodes;
+		let size = 1 + left.map_or(0, |left| nodes[left].size) + right.map_or(0, |right| nodes[right].size);
+		let aggregate = self.combine(left.map(|left| nodes[left].aggregate()), &nodes[node].value,
+		                             right.map(|right| nodes[right].aggregate()));
+		let reversed_aggregate = self.combine(right.map(|right| nodes[right].reversed_aggregate()), &nodes[node].value,
+		                                      left.map(|left| nodes[left].reversed_aggregate()));
+
+		let current = &mut self.nodes[node];
+		current.size = size;
+		current.aggregate = aggregate;
+		current.reversed_aggregate = reversed_aggregate;
+	}
+
+	/// Combines a value between two optional aggregates
+	///
+	/// Returns `None` if both aggregates are absent.
+	fn combine(&self, left: Option<&T>, value: &T, right: Option<&T>) -> Option<T> {
+		let aggregate = left.map(|left| (self.operator)(left, value));
+		match (aggregate, right) {
+			(Some(aggregate), Some(right)) => Some((self.operator)(&aggregate, right)),
+			(None, Some(right)) => Some((self.operator)(value, right)),
+			(aggregate, None) => aggregate,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Finds the vertices on the path between two vertices by searching the edges
+	fn find_path(edges: &[(usize, usize)], path: &mut Vec<usize>, target: usize) -> bool {
+		let current = *path.last().unwrap();
+		if current == target { return true; }
+		for &(a, b) in edges {
+			let next = if a == current { b } else if b == current { a } else { continue };
+			if path.contains(&next) { continue; }
+			path.push(next);
+			if find_path(edges, path, target) { return true; }
+			path.pop();
+		}
+		false
+	}
+
+	#[test]
+	fn test_forest() {
+		let sifter = |current: &i64, delta: &i64, length: usize| *current + (*delta * length as i64);
+		let mut values: Vec<i64> = (1..=8).collect();
+		let composer = |pending: &i64, delta: &i64| pending + delta;
+		let mut tree = LinkCutTree::construct(values.clone().into_iter(), |a: &i64, b: &i64| a + b, sifter, composer);
+		let mut edges: Vec<(usize, usize)> = Vec::new();
+
+		for step in 0..200usize {
+			let (a, b) = ((step * 5) % 8, (step * 3 + step / 8) % 8);
+			let mut path = vec![a];
+			let connected = find_path(&edges, &mut path, b);
+			assert_eq!(tree.connected(a, b), connected);
+
+			match step % 5 {
+				0 | 1 => {
+					assert_eq!(tree.link(a, b), !connected);
+					if !connected { edges.push((a, b)); }
+				}
+				2 => {
+					let position = edges.iter().position(|&edge| edge == (a, b) || edge == (b, a));
+					assert_eq!(tree.cut(a, b), position.is_some());
+					position.map(|position| edges.remove(position));
+				}
+				3 => {
+					assert_eq!(tree.update_path(a, b, &(step as i64)), connected);
+					if connected { path.iter().for_each(|&vertex| values[vertex] += step as i64); }
+				}
+				_ => {
+					let root = step % 8;
+					let mut path_a = vec![root];
+					let mut path_b = vec![root];
+					let expected = match find_path(&edges, &mut path_a, a) && find_path(&edges, &mut path_b, b) {
+						true => path_a.iter().zip(path_b.iter()).take_while(|(a, b)| a == b).last().map(|(a, _)| *a),
+						false => None,
+					};
+					assert_eq!(tree.lca(root, a, b), expected);
+				}
+			}
+
+			let mut path = vec![a];
+			let connected = find_path(&edges, &mut path, b);
+			let expected = if connected { Some(path.iter().map(|&vertex| values[vertex]).sum::<i64>()) } else { None };
+			assert_eq!(tree.query(a, b).cloned(), expected);
+		}
+	}
+
+	#[test]
+	fn test_composed() {
+		// Deltas are affine maps `x -> a * x + b`
+		let sifter = |current: &i64, delta: &(i64, i64), length: usize| {
+			delta.0 * *current + delta.1 * length as i64
+		};
+		let composer = |pending: &(i64, i64), delta: &(i64, i64)| {
+			(delta.0 * pending.0, delta.0 * pending.1 + delta.1)
+		};
+
+		// A path 0 - 1 - 2 - 3 - 4 with a branch 2 - 5
+		let mut tree = LinkCutTree::construct((0..6i64).collect::<Vec<_>>().into_iter(),
+		                                      |a: &i64, b: &i64| a + b, sifter, composer);
+		for &(a, b) in [(0, 1), (1, 2), (2, 3), (3, 4), (2, 5)].iter() {
+			assert!(tree.link(a, b));
+		}
+
+		tree.update_path(0, 4, &(2, 1));
+		tree.update_path(5, 3, &(3, 0));
+		assert_eq!(tree.query(0, 5), Some(&(1 + 3 + 15 + 15)));
+		assert_eq!(tree.update(2, 0), 15);
+		assert_eq!(tree.query(4, 5), Some(&(9 + 21 + 15)));
+		assert_eq!(tree.lca(0, 4, 5), Some(2));
+	}
+
+	#[test]
+	fn test_path_order() {
+		// Digits are concatenated so the direction of every query is visible
+		let operator = |a: &(u64, u64), b: &(u64, u64)| (a.0 * b.1 + b.0, a.1 * b.1);
+		let sifter = |current: &(u64, u64), _: &(), _: usize| *current;
+		let values: Vec<(u64, u64)> = (1..=8).map(|digit| (digit, 10)).collect();
+		let mut tree = LinkCutTree::construct(values.clone().into_iter(), operator, sifter, |_: &(), _: &()| ());
+		let mut edges: Vec<(usize, usize)> = Vec::new();
+
+		for step in 0..200usize {
+			let (a, b) = ((step * 5) % 8, (step * 3 + step / 8) % 8);
+			match step % 3 {
+				0 | 1 => if tree.link(a, b) { edges.push((a, b)); },
+				_ => if tree.cut(a, b) { edges.retain(|&edge| edge != (a, b) && edge != (b, a)); },
+			}
+
+			for (a, b) in (0..8).flat_map(|a| (0..8).map(move |b| (a, b))) {
+				let mut path = vec![a];
+				let expected = match find_path(&edges, &mut path, b) {
+					true => Some(path[1..].iter().fold(values[a], |aggregate, &vertex| operator(&aggregate, &values[vertex]))),
+					false => None,
+				};
+				assert_eq!(tree.query(a, b).cloned(), expected);
+			}
+		}
+	}
+}
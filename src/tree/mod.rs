@@ -1,15 +1,40 @@
+pub use self::arena_tree::ArenaTree;
 pub use self::backing_tree::BackingTree;
+pub use self::backing_tree::MutableBackingTree;
+pub use self::delta::DeltaComposer;
 pub use self::delta::DeltaSifter;
 pub use self::delta::DeltaWrapper;
+pub use self::dynamic_segment_tree::DynamicSegmentTree;
+pub use self::fenwick_tree::FenwickTree;
+pub use self::fenwick_tree::RangeFenwickTree;
 pub use self::implicit_tree::ImplicitTree;
+pub use self::implicit_treap::ImplicitTreap;
+pub use self::iterative_segment_tree::IterativeSegmentTree;
 pub use self::lazy_segment_tree::LazySegmentTree;
+pub use self::link_cut_tree::LinkCutTree;
+pub use self::persistent_segment_tree::PersistentSegmentTree;
 pub use self::segment_tree::SegmentTree;
+pub use self::segment_tree_2d::SegmentTree2D;
+pub use self::segment_tree_beats::SegmentTreeBeats;
+pub use self::traversal::LevelOrder;
+pub use self::traversal::PostOrder;
+pub use self::traversal::PreOrder;
 
 pub mod functions;
 mod delta;
 mod implicit_tree;
+mod implicit_treap;
 mod segment_tree;
 mod lazy_segment_tree;
+mod iterative_segment_tree;
+mod persistent_segment_tree;
+mod dynamic_segment_tree;
+mod segment_tree_beats;
+mod segment_tree_2d;
+mod fenwick_tree;
+mod link_cut_tree;
 mod backing_tree;
+mod arena_tree;
+mod traversal;
 
 pub const BINARY_WIDTH: usize = 2;
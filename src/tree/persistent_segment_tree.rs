@@ -0,0 +1,220 @@
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use operator::Monoid;
+use OwnedRef;
+use Vec;
+
+/// A node that may be shared between many versions
+struct PersistentNode<T> {
+	value: T,
+	children: Option<(usize, usize)>,
+}
+
+/// A variant of `SegmentTree` that keeps every previous version queryable
+///
+/// Updates do not modify the tree in place. Instead the path from the
+/// root to the updated value is copied and every other node is shared
+/// with the version that was updated:
+/// - Construct: O(`n`)
+/// - Query: O(`log n`)
+/// - Update: O(`log n`) time and additional space
+///
+/// Versions are numbered in the order they are created. The constructed
+/// tree is version `0` and every update returns the number of the new version.
+///
+/// A tree constructed from an empty input has no nodes. Every query on it is
+/// empty and answered with the identity of the operator.
+pub struct PersistentSegmentTree<T, O> {
+	nodes: Vec<PersistentNode<T>>,
+	roots: Vec<usize>,
+	operator: O,
+	length: usize,
+}
+
+impl<T, O> PersistentSegmentTree<T, O> where O: Monoid<T> {
+	pub fn construct(mut values: impl FixedDataSource<T>, operator: O) -> PersistentSegmentTree<T, O> {
+		let length = values.len();
+		let mut tree = PersistentSegmentTree {
+			nodes: Vec::with_capacity(2 * length),
+			roots: Vec::new(),
+			operator,
+			length,
+		};
+
+		// The root of an empty tree is never visited
+		let root = match length {
+			0 => 0,
+			_ => tree.construct_recursively(&mut values, 0, length - 1),
+		};
+		tree.roots.push(root);
+		tree
+	}
+
+	fn construct_recursively(&mut self, values: &mut impl FixedDataSource<T>,
+	                         range_left: usize, range_right: usize) -> usize {
+		if range_left == range_right {
+			return self.insert_node(values.next().unwrap(), None);
+		}
+
+		let (middle_left, middle_right) = super::functions::split_range(range_left, range_right);
+		let left_child = self.construct_recursively(values, range_left, middle_left);
+		let right_child = self.construct_recursively(values, middle_right, range_right);
+		let value = self.operator.operate(&self.nodes[left_child].value, &self.nodes[right_child].value);
+		self.insert_node(value, Some((left_child, right_child)))
+	}
+
+	fn insert_node(&mut self, value: T, children: Option<(usize, usize)>) -> usize {
+		self.nodes.push(PersistentNode { value, children });
+		self.nodes.len() - 1
+	}
+
+	pub fn len(&self) -> usize {
+		self.length
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.length == 0
+	}
+
+	/// Get the value for a range such as `2..5` or `..` as it was in `version`
+	///
+	/// The identity of the operator is returned for empty ranges.
+	///
+	/// # Panics
+	///
+	/// `version` must have been created by this tree
+	/// The range must be within the input array
+	/// The operator must have an identity if the range is empty
+	pub fn query(&self, version: usize, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(version, range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range as it was in `version`
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if it is empty and the operator has no identity
+	///
+	/// # Panics
+	///
+	/// `version` must have been created by this tree
+	pub fn try_query(&self, version: usize, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		assert!(version < self.roots.len());
+		let range = ::range::bounds(range, self.length)?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		let root = self.roots[version];
+		Ok(self.query_recursively(root, range.start, range.end - 1, 0, self.length - 1).unwrap())
+	}
+
+	fn query_recursively(&self, node: usize, left: usize, right: usize,
+	                     current_left: usize, current_right: usize) -> Option<OwnedRef<'_, T>> {
+		// Same as SegmentTree query_recursively
+		let in_range = left <= current_left && current_right <= right;
+		if in_range { return Some((&self.nodes[node].value).into()); }
+
+		let (left_child, right_child) = self.nodes[node].children.unwrap();
+		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+		let mut left_value = None;
+		let mut right_value = None;
+		if middle_left >= left {
+			left_value = self.query_recursively(left_child, left, right, current_left, middle_left);
+		}
+		if middle_right <= right {
+			right_value = self.query_recursively(right_child, left, right, middle_right, current_right);
+		}
+
+		match (left_value, right_value) {
+			(Some(left_value), Some(right_value)) => Some(self.operator.operate(&left_value, &right_value).into()),
+			(Some(left_value), None) => Some(left_value),
+			(None, Some(right_value)) => Some(right_value),
+			(None, None) => unreachable!(),
+		}
+	}
+
+	/// Creates a new version from `version` with the value at `index` replaced
+	/// and returns the new version
+	///
+	/// # Panics
+	///
+	/// `version` must have been created by this tree
+	/// `index` must be less than the length of the input array
+	pub fn update(&mut self, version: usize, index: usize, value: T) -> usize {
+		assert!(version < self.roots.len());
+		assert!(index < self.length);
+		let root = self.roots[version];
+		let last = self.length - 1;
+		let root = self.update_recursively(root, index, value, 0, last);
+		self.roots.push(root);
+		self.roots.len() - 1
+	}
+
+	fn update_recursively(&mut self, node: usize, index: usize, value: T,
+	                      current_left: usize, current_right: usize) -> usize {
+		if current_left == current_right {
+			return self.insert_node(value, None);
+		}
+
+		let (mut left_child, mut right_child) = self.nodes[node].children.unwrap();
+		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+		if index <= middle_left {
+			left_child = self.update_recursively(left_child, index, value, current_left, middle_left);
+		} else {
+			right_child = self.update_recursively(right_child, index, value, middle_right, current_right);
+		}
+
+		let value = self.operator.operate(&self.nodes[left_child].value, &self.nodes[right_child].value);
+		self.insert_node(value, Some((left_child, right_child)))
+	}
+
+	/// Returns the most recently created version
+	pub fn latest(&self) -> usize {
+		self.roots.len() - 1
+	}
+
+	/// Returns the number of versions that have been created
+	pub fn versions(&self) -> usize {
+		self.roots.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_summation() {
+		use core::ops::Deref;
+		let data = vec![1, 2, 3, 4, 5, 6, 7];
+		let mut tree = PersistentSegmentTree::construct(data.into_iter(), ::operator::summation());
+		let first = tree.update(0, 6, 10);
+		let second = tree.update(first, 0, 0);
+		let branch = tree.update(0, 3, 0);
+		assert_eq!(tree.versions(), 4);
+		assert_eq!(tree.latest(), branch);
+
+		assert_eq!(*tree.query(0, ..).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 7);
+		assert_eq!(*tree.query(0, 4..=5).deref(), 5 + 6);
+		assert_eq!(*tree.query(first, 4..).deref(), 5 + 6 + 10);
+		assert_eq!(*tree.query(first, ..).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 10);
+		assert_eq!(*tree.query(second, ..).deref(), 2 + 3 + 4 + 5 + 6 + 10);
+		assert_eq!(*tree.query(second, 0..=0).deref(), 0);
+		assert_eq!(*tree.query(branch, ..).deref(), 1 + 2 + 3 + 5 + 6 + 7);
+		assert_eq!(*tree.query(branch, 6..7).deref(), 7);
+		assert_eq!(tree.try_query(branch, 3..8).err(), Some(Error::OutOfBounds { index: 7, length: 7 }));
+		assert_eq!(tree.try_query(branch, 3..3).err(), Some(Error::EmptyRange));
+	}
+
+	#[test]
+	fn test_empty() {
+		use core::ops::Deref;
+		let tree = PersistentSegmentTree::construct(::core::iter::empty(), ::operator::summation_default::<i32>());
+		assert!(tree.is_empty());
+		assert_eq!(*tree.query(0, ..).deref(), 0);
+		assert_eq!(tree.try_query(0, ..=0).err(), Some(Error::OutOfBounds { index: 0, length: 0 }));
+	}
+}
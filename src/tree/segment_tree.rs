@@ -1,8 +1,11 @@
+use core::ops::RangeBounds;
+use Error;
 use FixedDataSource;
-use operator::AssociativeOperator;
+use operator::Monoid;
 use OwnedRef;
 use super::BackingTree;
 use super::ImplicitTree;
+use Vec;
 
 /// `SegmentTree` efficiently calculates a value in a range
 ///
@@ -14,6 +17,10 @@ use super::ImplicitTree;
 /// - Query: O(`log n`)
 /// - Update: O(`log n`)
 ///
+/// Values can be appended with `push` and removed from the end with `pop`.
+/// The tree keeps spare leaves holding the identity of the operator and
+/// is rebuilt when the capacity changes.
+///
 /// If updates need to be done over a range then `LazySegmentTree` may
 /// be more appropriate.
 ///
@@ -24,10 +31,12 @@ pub struct SegmentTree<T, B, O> where B: BackingTree {
 	tree: B,
 	operator: O,
 	length: usize,
+	capacity: usize,
+	width: usize,
 	_type: ::core::marker::PhantomData<T>,
 }
 
-impl<T, B, O> SegmentTree<T, B, O> where O: AssociativeOperator<T>, B: BackingTree<Value=T> {
+impl<T, B, O> SegmentTree<T, B, O> where O: Monoid<T>, B: BackingTree<Value=T> {
 	fn construct_recursively(values: &mut impl FixedDataSource<T>, operator: &mut O, tree: &mut ImplicitTree<T>,
 	                         range_left: usize, range_right: usize, node: usize) {
 		if range_right == range_left {
@@ -35,107 +44,399 @@ impl<T, B, O> SegmentTree<T, B, O> where O: AssociativeOperator<T>, B: BackingTr
 			return;
 		}
 
-		let (middle_left, middle_right) = super::functions::split_range(range_left, range_right);
-		let left_child = tree.child(&node, 0);
-		let right_child = tree.child(&node, 1);
-		Self::construct_recursively(values, operator, tree, middle_right, range_right, right_child);
-		Self::construct_recursively(values, operator, tree, range_left, middle_left, left_child);
-		let value = operator(tree.get(&left_child).unwrap(), tree.get(&right_child).unwrap());
+		let width = tree.width();
+		let ranges = super::functions::split_range_width(range_left, range_right, width);
+		for (index, (child_left, child_right)) in ranges.enumerate().rev() {
+			let child = tree.child(&node, index);
+			Self::construct_recursively(values, operator, tree, child_left, child_right, child);
+		}
+
+		let value = combine_children(operator, tree, &node, width, range_left, range_right);
 		unsafe { tree.insert_unchecked(node, value) };
 	}
 
-	/// Get the value for a range
+	/// Get the value for a range such as `2..5` or `..`
+	///
+	/// The identity of the operator is returned for empty ranges.
 	///
 	/// # Panics
 	///
-	/// `left` cannot be greater than `right`
-	/// `right` must be less than the length of the input array
-	pub fn query(&self, left: usize, right: usize) -> OwnedRef<T> {
-		assert!(left <= right && right < self.length);
-		self.query_recursively(self.tree.root(), left, right, 0, self.length - 1).unwrap()
+	/// The range must be within the input array
+	/// The operator must have an identity if the range is empty
+	pub fn query(&self, range: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array or
+	/// if it is empty and the operator has no identity
+	pub fn try_query(&self, range: impl RangeBounds<usize>) -> Result<OwnedRef<'_, T>, Error> {
+		let range = ::range::bounds(range, self.length)?;
+		if range.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		let root = self.tree.root();
+		Ok(self.query_recursively(root, range.start, range.end - 1, 0, self.capacity - 1).unwrap())
+	}
+
+	fn identity(&self) -> T {
+		self.operator.identity().expect("operator has no identity for an empty range")
 	}
 
 	fn query_recursively(&self, node: B::Identifier, left: usize, right: usize,
 	                     current_left: usize, current_right: usize) -> Option<OwnedRef<T>> {
+		if right < current_left || current_right < left { return None; }
 		let in_range = left <= current_left && current_right <= right;
-		if in_range { return self.tree.get(&node).and_then(|reference| Some(reference.into())); }
+		if in_range { return self.tree.get(&node).map(|reference| reference.into()); }
 
-		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
-		let mut left_value = None;
-		let mut right_value = None;
-		if middle_left >= left {
-			let left_child = self.tree.child(&node, 0);
-			left_value = self.query_recursively(left_child, left, right, current_left, middle_left);
+		let mut value: Option<OwnedRef<T>> = None;
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			let child = self.tree.child(&node, index);
+			value = match (value, self.query_recursively(child, left, right, child_left, child_right)) {
+				(Some(value), Some(child_value)) => Some(self.operator.operate(&value, &child_value).into()),
+				(value, None) => value,
+				(None, child_value) => child_value,
+			};
 		}
-		if middle_right <= right {
-			let right_child = self.tree.child(&node, 1);
-			right_value = self.query_recursively(right_child, left, right, middle_right, right);
+		value
+	}
+
+	/// Finds the furthest `right` such that `predicate` holds for the range `left..=right`
+	///
+	/// `predicate` must be monotone: if it holds for a range then it must also
+	/// hold for every shorter range starting at `left`. The tree is descended
+	/// once so the search takes O(`log n`) rather than the O(`log² n`) of
+	/// binary searching over `query`.
+	///
+	/// # Errors
+	///
+	/// Returns `None` if `predicate` does not hold for the range `left..=left`
+	///
+	/// # Panics
+	///
+	/// `left` must be less than the length of the input array
+	pub fn max_right<F>(&self, left: usize, predicate: F) -> Option<usize> where F: Fn(&T) -> bool {
+		assert!(left < self.length);
+		let mut accumulator = None;
+		let root = self.tree.root();
+		match self.max_right_recursively(root, left, 0, self.capacity - 1, &mut accumulator, &predicate) {
+			Some(boundary) if boundary == left => None,
+			Some(boundary) => Some(boundary - 1),
+			None => Some(self.length - 1),
 		}
+	}
 
-		if let Some(left_value) = left_value {
-			if let Some(right_value) = right_value {
-				Some((self.operator)(&left_value, &right_value).into())
-			} else {
-				Some(left_value)
+	/// Returns the first index at which `predicate` fails or `None` if
+	/// it holds for the entire node
+	fn max_right_recursively<'a, F>(&'a self, node: B::Identifier, left: usize, current_left: usize,
+	                                current_right: usize, accumulator: &mut Option<OwnedRef<'a, T>>,
+	                                predicate: &F) -> Option<usize> where F: Fn(&T) -> bool {
+		if current_right < left { return None; }
+		if left <= current_left {
+			let value = self.tree.get(&node).unwrap();
+			let candidate: OwnedRef<T> = match accumulator {
+				Some(accumulator) => self.operator.operate(accumulator, value).into(),
+				None => value.into(),
+			};
+
+			if predicate(&candidate) {
+				*accumulator = Some(candidate);
+				return None;
+			} else if current_left == current_right {
+				return Some(current_left);
 			}
-		} else if let Some(right_value) = right_value {
-			Some(right_value.into())
-		} else {
-			unreachable!()
+		}
+
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			let child = self.tree.child(&node, index);
+			let boundary = self.max_right_recursively(child, left, child_left, child_right, accumulator, predicate);
+			if boundary.is_some() { return boundary; }
+		}
+		None
+	}
+
+	/// Finds the nearest `left` such that `predicate` holds for the range `left..=right`
+	///
+	/// This is the mirror of `max_right` and has the same requirements on `predicate`.
+	///
+	/// # Errors
+	///
+	/// Returns `None` if `predicate` does not hold for the range `right..=right`
+	///
+	/// # Panics
+	///
+	/// `right` must be less than the length of the input array
+	pub fn min_left<F>(&self, right: usize, predicate: F) -> Option<usize> where F: Fn(&T) -> bool {
+		assert!(right < self.length);
+		let mut accumulator = None;
+		let root = self.tree.root();
+		match self.min_left_recursively(root, right, 0, self.capacity - 1, &mut accumulator, &predicate) {
+			Some(boundary) if boundary == right => None,
+			Some(boundary) => Some(boundary + 1),
+			None => Some(0),
 		}
 	}
 
+	/// Returns the last index at which `predicate` fails or `None` if
+	/// it holds for the entire node
+	fn min_left_recursively<'a, F>(&'a self, node: B::Identifier, right: usize, current_left: usize,
+	                               current_right: usize, accumulator: &mut Option<OwnedRef<'a, T>>,
+	                               predicate: &F) -> Option<usize> where F: Fn(&T) -> bool {
+		if current_left > right { return None; }
+		if current_right <= right {
+			let value = self.tree.get(&node).unwrap();
+			let candidate: OwnedRef<T> = match accumulator {
+				Some(accumulator) => self.operator.operate(value, accumulator).into(),
+				None => value.into(),
+			};
+
+			if predicate(&candidate) {
+				*accumulator = Some(candidate);
+				return None;
+			} else if current_left == current_right {
+				return Some(current_left);
+			}
+		}
+
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate().rev() {
+			let child = self.tree.child(&node, index);
+			let boundary = self.min_left_recursively(child, right, child_left, child_right, accumulator, predicate);
+			if boundary.is_some() { return boundary; }
+		}
+		None
+	}
+
 	/// Updates a value
 	///
 	/// # Panics
 	///
 	/// `index` must be less than the length of the input array
 	pub fn update(&mut self, index: usize, value: T) {
-		assert!(index < self.length);
+		self.try_update(index, value).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Updates a value
+	///
+	/// # Errors
+	///
+	/// Returns an error if `index` is not less than the length of the input array
+	pub fn try_update(&mut self, index: usize, value: T) -> Result<(), Error> {
+		if index >= self.length { return Err(Error::OutOfBounds { index, length: self.length }); }
 		let root = self.tree.root();
-		let last = self.length - 1;
+		let last = self.capacity - 1;
 		self.update_recursively(root, index, value, 0, last);
+		Ok(())
 	}
 
+	/// Replaces a value and returns the previous value
 	fn update_recursively(&mut self, node: B::Identifier, index: usize, value: T,
-	                      current_left: usize, current_right: usize) {
+	                      current_left: usize, current_right: usize) -> T {
 		use core::mem::replace;
 		if index == current_left && current_left == current_right {
-			replace(self.tree.get_mut(&node).unwrap(), value);
-			return;
+			return replace(self.tree.get_mut(&node).unwrap(), value);
+		}
+
+		let mut ranges = super::functions::split_range_width(current_left, current_right, self.width).enumerate();
+		let (child_index, (child_left, child_right)) = ranges.find(|(_, (_, child_right))| index <= *child_right).unwrap();
+		let child = self.tree.child(&node, child_index);
+		let previous = self.update_recursively(child, index, value, child_left, child_right);
+
+		let value = combine_children(&self.operator, &self.tree, &node, self.width, current_left, current_right);
+		*self.tree.get_mut(&node).unwrap() = value;
+		previous
+	}
+
+	pub fn len(&self) -> usize {
+		self.length
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.length == 0
+	}
+
+	/// Iterates over the values in order
+	pub fn iter(&self) -> impl Iterator<Item=&T> {
+		let mut leaves = Vec::with_capacity(self.length);
+		if self.length > 0 {
+			self.leaves(self.tree.root(), 0, self.capacity - 1, &mut leaves);
 		}
+		leaves.into_iter().map(move |node| self.tree.get(&node).unwrap())
+	}
 
-		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
-		if index <= middle_left {
-			let left_child = self.tree.child(&node, 0);
-			self.update_recursively(left_child, index, value, current_left, middle_left);
-		} else {
-			let right_child = self.tree.child(&node, 1);
-			self.update_recursively(right_child, index, value, middle_right, current_right);
+	/// Collects the leaves that hold values in order
+	fn leaves(&self, node: B::Identifier, current_left: usize, current_right: usize,
+	          leaves: &mut Vec<B::Identifier>) {
+		if current_left >= self.length { return; }
+		if current_left == current_right {
+			leaves.push(node);
+			return;
 		}
 
-		let value = {
-			let left_child = self.tree.get(&self.tree.child(&node, 0)).unwrap();
-			let right_child = self.tree.get(&self.tree.child(&node, 1)).unwrap();
-			(self.operator)(left_child, right_child)
-		};
-		replace(self.tree.get_mut(&node).unwrap(), value);
+		let ranges = super::functions::split_range_width(current_left, current_right, self.width);
+		for (index, (child_left, child_right)) in ranges.enumerate() {
+			self.leaves(self.tree.child(&node, index), child_left, child_right, leaves);
+		}
 	}
 }
 
-impl<T, O> SegmentTree<T, ImplicitTree<T>, O> where O: AssociativeOperator<T> {
-	pub fn construct_implicit(mut values: impl FixedDataSource<T>, mut operator: O) -> SegmentTree<T, ImplicitTree<T>, O> {
-		let mut tree = ImplicitTree::new(super::BINARY_WIDTH);
+impl<T, O> SegmentTree<T, ImplicitTree<T>, O> where O: Monoid<T> {
+	pub fn construct_implicit(values: impl FixedDataSource<T>, operator: O) -> SegmentTree<T, ImplicitTree<T>, O> {
+		Self::construct_with_width(values, operator, super::BINARY_WIDTH)
+	}
+
+	/// Constructs a tree where every node has up to `width` children
+	///
+	/// Wider trees are shallower which can be faster for large arrays as
+	/// fewer nodes are visited. Queries and updates take O(`width log n / log width`).
+	///
+	/// # Panics
+	///
+	/// `width` must be at least two
+	pub fn construct_with_width(mut values: impl FixedDataSource<T>, mut operator: O, width: usize)
+	                            -> SegmentTree<T, ImplicitTree<T>, O> {
+		assert!(width >= 2);
+		let mut tree = ImplicitTree::new(width);
 		let root = tree.root();
 		let length = values.len();
-		Self::construct_recursively(&mut values, &mut operator, &mut tree, 0, length - 1, root);
+		if length > 0 {
+			Self::construct_recursively(&mut values, &mut operator, &mut tree, 0, length - 1, root);
+		}
+
 		SegmentTree {
 			tree,
 			operator,
 			length,
+			capacity: length,
+			width,
 			_type: Default::default(),
 		}
 	}
+
+	/// Appends a value to the end
+	///
+	/// The tree is rebuilt with double the capacity when it is full
+	/// so this takes amortised O(`log n`).
+	///
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn push(&mut self, value: T) {
+		if self.length == self.capacity {
+			let capacity = ::core::cmp::max(1, self.capacity * 2);
+			self.resize(capacity);
+		}
+
+		self.length += 1;
+		let root = self.tree.root();
+		let (index, last) = (self.length - 1, self.capacity - 1);
+		self.update_recursively(root, index, value, 0, last);
+	}
+
+	/// Removes the last value and returns it
+	///
+	/// The tree is rebuilt with half the capacity when it is a quarter
+	/// full so this takes amortised O(`log n`).
+	///
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn pop(&mut self) -> Option<T> {
+		if self.length == 0 { return None; }
+		let identity = self.identity();
+		let root = self.tree.root();
+		let (index, last) = (self.length - 1, self.capacity - 1);
+		let value = self.update_recursively(root, index, identity, 0, last);
+
+		self.length -= 1;
+		if self.length * 4 <= self.capacity {
+			let capacity = self.capacity / 2;
+			self.resize(capacity);
+		}
+		Some(value)
+	}
+
+	/// Appends every value to the end
+	///
+	/// # Panics
+	///
+	/// The operator must have an identity
+	pub fn extend<I>(&mut self, values: I) where I: IntoIterator<Item=T> {
+		values.into_iter().for_each(|value| self.push(value));
+	}
+
+	/// Converts the tree into its values in order
+	pub fn into_vec(mut self) -> Vec<T> {
+		self.take_values()
+	}
+
+	/// Rebuilds the tree over `capacity` leaves where the leaves
+	/// after the values hold the identity
+	fn resize(&mut self, capacity: usize) {
+		let mut values = self.take_values();
+		super::functions::extend_until(&mut values, capacity, &mut || self.identity());
+
+		let mut tree = ImplicitTree::new(self.width);
+		if capacity > 0 {
+			let root = tree.root();
+			Self::construct_recursively(&mut values.into_iter(), &mut self.operator, &mut tree, 0, capacity - 1, root);
+		}
+
+		self.tree = tree;
+		self.capacity = capacity;
+	}
+
+	fn take_values(&mut self) -> Vec<T> {
+		let mut leaves = Vec::with_capacity(self.length);
+		if self.length > 0 {
+			self.leaves(self.tree.root(), 0, self.capacity - 1, &mut leaves);
+		}
+
+		let tree = &mut self.tree;
+		leaves.into_iter().map(|node| unsafe { tree.remove_unchecked(node) }.unwrap()).collect()
+	}
+}
+
+/// Combines the values of every child of a node
+fn combine_children<T, B, O>(operator: &O, tree: &B, node: &B::Identifier, width: usize,
+                             current_left: usize, current_right: usize) -> T
+	where O: Monoid<T>, B: BackingTree<Value=T> {
+	// A node that is not a leaf always has at least two children
+	let count = super::functions::split_range_width(current_left, current_right, width).len();
+	let mut children = (0..count).map(|index| tree.get(&tree.child(node, index)).unwrap());
+	let (first, second) = (children.next().unwrap(), children.next().unwrap());
+	children.fold(operator.operate(first, second), |value, child| operator.operate(&value, child))
+}
+
+/// The operator is not serialized and must be given again with `deserialize`
+#[cfg(feature = "serde")]
+impl<T, B, O> ::serde::Serialize for SegmentTree<T, B, O> where B: BackingTree + ::serde::Serialize {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: ::serde::Serializer {
+		(&self.tree, self.length, self.capacity, self.width).serialize(serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<T, B, O> SegmentTree<T, B, O> where B: BackingTree {
+	/// Deserializes a tree with the operator it was constructed with
+	pub fn deserialize<'de, D>(deserializer: D, operator: O) -> Result<SegmentTree<T, B, O>, D::Error>
+		where D: ::serde::Deserializer<'de>, B: ::serde::Deserialize<'de> {
+		let (tree, length, capacity, width) = ::serde::Deserialize::deserialize(deserializer)?;
+		Ok(SegmentTree {
+			tree,
+			operator,
+			length,
+			capacity,
+			width,
+			_type: Default::default(),
+		})
+	}
 }
 
 impl<T, B, O> ::core::fmt::Debug for SegmentTree<T, B, O> where B: ::core::fmt::Debug + BackingTree {
@@ -153,15 +454,16 @@ mod tests {
 		use core::ops::Deref;
 		let data = vec![1, 2, 3, 4, 5, 6, 7];
 		let mut tree = SegmentTree::construct_implicit(data.into_iter(), ::operator::summation());
-		assert_eq!(*tree.query(0, 3).deref(), 1 + 2 + 3 + 4);
-		assert_eq!(*tree.query(0, 6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 7);
-		assert_eq!(*tree.query(4, 6).deref(), 5 + 6 + 7);
-		assert_eq!(*tree.query(0, 0).deref(), 1);
-		assert_eq!(*tree.query(2, 3).deref(), 3 + 4);
+		assert_eq!(*tree.query(0..=3).deref(), 1 + 2 + 3 + 4);
+		assert_eq!(*tree.query(0..=6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 7);
+		assert_eq!(*tree.query(4..=6).deref(), 5 + 6 + 7);
+		assert_eq!(*tree.query(0..=0).deref(), 1);
+		assert_eq!(*tree.query(2..=3).deref(), 3 + 4);
+		assert_eq!(*tree.query(4..=5).deref(), 5 + 6);
 		tree.update(6, 10);
-		assert_eq!(*tree.query(6, 6).deref(), 10);
-		assert_eq!(*tree.query(4, 6).deref(), 5 + 6 + 10);
-		assert_eq!(*tree.query(0, 6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 10);
+		assert_eq!(*tree.query(6..=6).deref(), 10);
+		assert_eq!(*tree.query(4..=6).deref(), 5 + 6 + 10);
+		assert_eq!(*tree.query(0..=6).deref(), 1 + 2 + 3 + 4 + 5 + 6 + 10);
 	}
 
 	#[test]
@@ -169,14 +471,139 @@ mod tests {
 		use core::ops::Deref;
 		let data = vec![1, 2, 3, 4, 5, 6, 7];
 		let mut tree = SegmentTree::construct_implicit(data.into_iter(), ::operator::minimum());
-		assert_eq!(*tree.query(0, 3).deref(), 1);
-		assert_eq!(*tree.query(0, 6).deref(), 1);
-		assert_eq!(*tree.query(4, 6).deref(), 5);
-		assert_eq!(*tree.query(0, 0).deref(), 1);
-		assert_eq!(*tree.query(2, 3).deref(), 3);
+		assert_eq!(*tree.query(0..=3).deref(), 1);
+		assert_eq!(*tree.query(0..=6).deref(), 1);
+		assert_eq!(*tree.query(4..=6).deref(), 5);
+		assert_eq!(*tree.query(0..=0).deref(), 1);
+		assert_eq!(*tree.query(2..=3).deref(), 3);
 		tree.update(6, 10);
-		assert_eq!(*tree.query(6, 6).deref(), 10);
-		assert_eq!(*tree.query(4, 6).deref(), 5);
-		assert_eq!(*tree.query(0, 6).deref(), 1);
+		assert_eq!(*tree.query(6..=6).deref(), 10);
+		assert_eq!(*tree.query(4..=6).deref(), 5);
+		assert_eq!(*tree.query(0..=6).deref(), 1);
+	}
+
+	#[test]
+	fn test_partial_ranges() {
+		use core::ops::Deref;
+		let data = vec![3, 1, 4, 1, 5, 9, 2, 6];
+		let tree = SegmentTree::construct_implicit(data.clone().into_iter(), ::operator::summation());
+		for left in 0..data.len() {
+			for right in left..data.len() {
+				assert_eq!(*tree.query(left..=right).deref(), data[left..=right].iter().sum::<i32>());
+			}
+		}
+	}
+
+	#[test]
+	fn test_binary_search() {
+		let data = vec![3, 1, 4, 1, 5, 9, 2, 6];
+		let tree = SegmentTree::construct_implicit(data.clone().into_iter(), ::operator::summation());
+		for left in 0..data.len() {
+			for limit in 0..32 {
+				let expected = (left..data.len()).take_while(|right| {
+					data[left..=*right].iter().sum::<i32>() <= limit
+				}).last();
+				assert_eq!(tree.max_right(left, |sum| *sum <= limit), expected);
+			}
+		}
+
+		for right in 0..data.len() {
+			for limit in 0..32 {
+				let expected = (0..=right).rev().take_while(|left| {
+					data[*left..=right].iter().sum::<i32>() <= limit
+				}).last();
+				assert_eq!(tree.min_left(right, |sum| *sum <= limit), expected);
+			}
+		}
+	}
+
+	#[test]
+	fn test_empty() {
+		use core::ops::Deref;
+		let tree = SegmentTree::construct_implicit(::core::iter::empty::<u32>(), ::operator::minimum_bounded());
+		assert_eq!(*tree.query(..).deref(), u32::MAX);
+
+		let data = vec![1, 2, 3];
+		let tree = SegmentTree::construct_implicit(data.into_iter(), ::operator::summation_default());
+		assert_eq!(*tree.query(2..2).deref(), 0);
+		assert_eq!(*tree.query(3..3).deref(), 0);
+		assert_eq!(*tree.query(0..=2).deref(), 1 + 2 + 3);
+	}
+
+	#[test]
+	fn test_fallible() {
+		use core::ops::Deref;
+		let data = vec![1, 2, 3, 4];
+		let mut tree = SegmentTree::construct_implicit(data.into_iter(), |a: &i32, b: &i32| a + b);
+		assert_eq!(*tree.try_query(1..).unwrap().deref(), 2 + 3 + 4);
+		assert_eq!(*tree.try_query(..).unwrap().deref(), 1 + 2 + 3 + 4);
+		assert_eq!(tree.try_query(2..2).err(), Some(Error::EmptyRange));
+		assert_eq!(tree.try_query(1..=4).err(), Some(Error::OutOfBounds { index: 4, length: 4 }));
+		assert_eq!(tree.try_update(4, 0), Err(Error::OutOfBounds { index: 4, length: 4 }));
+		assert_eq!(tree.try_update(3, 0), Ok(()));
+		assert_eq!(*tree.query(..).deref(), 1 + 2 + 3);
+	}
+
+	#[test]
+	fn test_growth() {
+		use core::ops::Deref;
+		let mut tree = SegmentTree::construct_implicit(vec![4, 2].into_iter(), ::operator::minimum_bounded());
+		let mut data = vec![4, 2];
+		tree.extend(vec![7, 1, 8, 3, 6]);
+		data.extend(vec![7, 1, 8, 3, 6]);
+		assert!(tree.iter().eq(data.iter()));
+
+		while !data.is_empty() {
+			assert_eq!(tree.len(), data.len());
+			for right in 0..data.len() {
+				for left in 0..=right {
+					assert_eq!(*tree.query(left..=right).deref(), *data[left..=right].iter().min().unwrap());
+				}
+			}
+			assert_eq!(tree.pop(), data.pop());
+		}
+
+		assert!(tree.is_empty());
+		assert_eq!(tree.pop(), None);
+		tree.push(5);
+		assert_eq!(tree.into_vec(), vec![5]);
+	}
+
+	#[test]
+	fn test_width() {
+		use core::ops::Deref;
+		for width in 2..=8 {
+			let mut data = vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7];
+			let mut tree = SegmentTree::construct_with_width(data.clone().into_iter(), ::operator::summation_default(), width);
+			tree.update(4, 0);
+			data[4] = 0;
+			tree.push(10);
+			data.push(10);
+
+			for right in 0..data.len() {
+				for left in 0..=right {
+					assert_eq!(*tree.query(left..=right).deref(), data[left..=right].iter().sum::<i32>());
+				}
+				let expected = (0..=right).rev().take_while(|left| data[*left..=right].iter().sum::<i32>() <= 20).last();
+				assert_eq!(tree.min_left(right, |sum| *sum <= 20), expected);
+			}
+			assert!(tree.iter().eq(data.iter()));
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_serde() {
+		use core::ops::Deref;
+		let data = vec![5, 3, 8, 1, 9];
+		let tree = SegmentTree::construct_with_width(data.into_iter(), ::operator::minimum_bounded(), 3);
+		let serialized = ::serde_json::to_string(&tree).unwrap();
+		let mut deserializer = ::serde_json::Deserializer::from_str(&serialized);
+		let mut tree: SegmentTree<i32, ImplicitTree<i32>, _> =
+			SegmentTree::deserialize(&mut deserializer, ::operator::minimum_bounded()).unwrap();
+		assert_eq!(*tree.query(..).deref(), 1);
+		assert_eq!(*tree.query(4..).deref(), 9);
+		tree.push(0);
+		assert_eq!(*tree.query(4..).deref(), 0);
 	}
 }
@@ -0,0 +1,364 @@
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use operator::Monoid;
+use OwnedRef;
+use super::BackingTree;
+use super::ImplicitTree;
+use Vec;
+
+/// A tree over the rows where every node holds a tree over the columns
+type RowTree<T> = ImplicitTree<ImplicitTree<T>>;
+
+/// A two dimensional `SegmentTree` for rectangle queries over a grid
+///
+/// Each node of the tree over the rows holds a segment tree over the
+/// columns. The column tree of a node is the element-wise combination of
+/// the column trees of its children. The operator is expected to be
+/// commutative when combining across rows and columns:
+/// - Construct: O(`rows * columns`)
+/// - Query: O(`log rows * log columns`)
+/// - Update: O(`log rows * log columns`)
+///
+/// Positions are given as `(row, column)`. A grid with no rows or with
+/// rows of no columns can be constructed but has no positions to query or update.
+pub struct SegmentTree2D<T, O> {
+	tree: RowTree<T>,
+	operator: O,
+	rows: usize,
+	columns: usize,
+}
+
+impl<T, O> SegmentTree2D<T, O> where O: Monoid<T> {
+	/// Constructs a tree from a source of rows
+	///
+	/// # Panics
+	///
+	/// Every row must have the same length
+	pub fn construct_implicit<R>(mut values: impl FixedDataSource<R>, operator: O) -> SegmentTree2D<T, O>
+		where R: FixedDataSource<T> {
+		let rows = values.len();
+		let mut tree = SegmentTree2D {
+			tree: ImplicitTree::new(super::BINARY_WIDTH),
+			operator,
+			rows,
+			columns: 0,
+		};
+
+		if rows > 0 {
+			let root = tree.tree.root();
+			tree.construct_rows(&mut values, 0, rows - 1, root);
+		}
+		tree
+	}
+
+	pub fn rows(&self) -> usize {
+		self.rows
+	}
+
+	pub fn columns(&self) -> usize {
+		self.columns
+	}
+
+	fn construct_rows<R>(&mut self, values: &mut impl FixedDataSource<R>, range_left: usize,
+	                     range_right: usize, node: usize) where R: FixedDataSource<T> {
+		if range_left == range_right {
+			// Rows are constructed in order so the first row determines the width
+			let mut row = values.next().unwrap();
+			if range_left == 0 { self.columns = row.len(); }
+			assert_eq!(row.len(), self.columns, "rows must have the same length");
+
+			let mut columns = ImplicitTree::new(super::BINARY_WIDTH);
+			if self.columns > 0 {
+				let root = columns.root();
+				self.construct_columns(&mut row, &mut columns, 0, self.columns - 1, root);
+			}
+			unsafe { self.tree.insert_unchecked(node, columns) };
+			return;
+		}
+
+		let (middle_left, middle_right) = super::functions::split_range(range_left, range_right);
+		let left_child = self.tree.child(&node, 0);
+		let right_child = self.tree.child(&node, 1);
+		self.construct_rows(values, range_left, middle_left, left_child);
+		self.construct_rows(values, middle_right, range_right, right_child);
+
+		let mut columns = ImplicitTree::new(super::BINARY_WIDTH);
+		if self.columns > 0 {
+			let left_columns = self.tree.get(&left_child).unwrap();
+			let right_columns = self.tree.get(&right_child).unwrap();
+			let root = columns.root();
+			self.combine_columns(left_columns, right_columns, &mut columns, 0, self.columns - 1, root);
+		}
+		unsafe { self.tree.insert_unchecked(node, columns) };
+	}
+
+	fn construct_columns(&self, values: &mut impl FixedDataSource<T>, tree: &mut ImplicitTree<T>,
+	                     range_left: usize, range_right: usize, node: usize) {
+		if range_left == range_right {
+			unsafe { tree.insert_unchecked(node, values.next().unwrap()) };
+			return;
+		}
+
+		let (middle_left, middle_right) = super::functions::split_range(range_left, range_right);
+		let left_child = tree.child(&node, 0);
+		let right_child = tree.child(&node, 1);
+		self.construct_columns(values, tree, range_left, middle_left, left_child);
+		self.construct_columns(values, tree, middle_right, range_right, right_child);
+		let value = self.operator.operate(tree.get(&left_child).unwrap(), tree.get(&right_child).unwrap());
+		unsafe { tree.insert_unchecked(node, value) };
+	}
+
+	fn combine_columns(&self, left: &ImplicitTree<T>, right: &ImplicitTree<T>, tree: &mut ImplicitTree<T>,
+	                   range_left: usize, range_right: usize, node: usize) {
+		let value = self.operator.operate(left.get(&node).unwrap(), right.get(&node).unwrap());
+		unsafe { tree.insert_unchecked(node, value) };
+		if range_left == range_right { return; }
+
+		let (middle_left, middle_right) = super::functions::split_range(range_left, range_right);
+		self.combine_columns(left, right, tree, range_left, middle_left, tree.child(&node, 0));
+		self.combine_columns(left, right, tree, middle_right, range_right, tree.child(&node, 1));
+	}
+
+	/// Get the value for the rectangle between two corners inclusively
+	///
+	/// # Panics
+	///
+	/// `top_left` cannot be below or to the right of `bottom_right`
+	/// `bottom_right` must be within the grid
+	pub fn query(&self, top_left: (usize, usize), bottom_right: (usize, usize)) -> OwnedRef<'_, T> {
+		let (top, left) = top_left;
+		let (bottom, right) = bottom_right;
+		assert!(top <= bottom && left <= right);
+		self.query_ranges(top..=bottom, left..=right)
+	}
+
+	/// Get the value for the rectangle covering a range of rows and a range of columns
+	///
+	/// The identity of the operator is returned for empty rectangles.
+	///
+	/// # Panics
+	///
+	/// Both ranges must be within the grid
+	/// The operator must have an identity if either range is empty
+	pub fn query_ranges(&self, rows: impl RangeBounds<usize>, columns: impl RangeBounds<usize>) -> OwnedRef<'_, T> {
+		self.try_query_ranges(rows, columns).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the value for the rectangle covering a range of rows and a range of columns
+	///
+	/// # Errors
+	///
+	/// Returns an error if either range is not within the grid or
+	/// if either is empty and the operator has no identity
+	pub fn try_query_ranges(&self, rows: impl RangeBounds<usize>, columns: impl RangeBounds<usize>)
+	                        -> Result<OwnedRef<'_, T>, Error> {
+		let rows = ::range::bounds(rows, self.rows)?;
+		let columns = ::range::bounds(columns, self.columns)?;
+		if rows.is_empty() || columns.is_empty() {
+			return self.operator.identity().map(|identity| identity.into()).ok_or(Error::EmptyRange);
+		}
+
+		let root = self.tree.root();
+		let (top_left, bottom_right) = ((rows.start, columns.start), (rows.end - 1, columns.end - 1));
+		Ok(self.query_rows(root, top_left, bottom_right, 0, self.rows - 1).unwrap())
+	}
+
+	fn query_rows(&self, node: usize, top_left: (usize, usize), bottom_right: (usize, usize),
+	              current_top: usize, current_bottom: usize) -> Option<OwnedRef<'_, T>> {
+		let (top, left) = top_left;
+		let (bottom, right) = bottom_right;
+		if bottom < current_top || current_bottom < top { return None; }
+		if top <= current_top && current_bottom <= bottom {
+			let columns = self.tree.get(&node).unwrap();
+			return self.query_columns(columns, columns.root(), left, right, 0, self.columns - 1);
+		}
+
+		let (middle_top, middle_bottom) = super::functions::split_range(current_top, current_bottom);
+		let top_child = self.tree.child(&node, 0);
+		let bottom_child = self.tree.child(&node, 1);
+		let top_value = self.query_rows(top_child, top_left, bottom_right, current_top, middle_top);
+		let bottom_value = self.query_rows(bottom_child, top_left, bottom_right, middle_bottom, current_bottom);
+		self.combine(top_value, bottom_value)
+	}
+
+	fn query_columns<'a>(&'a self, tree: &'a ImplicitTree<T>, node: usize, left: usize, right: usize,
+	                     current_left: usize, current_right: usize) -> Option<OwnedRef<'a, T>> {
+		if right < current_left || current_right < left { return None; }
+		if left <= current_left && current_right <= right {
+			return tree.get(&node).map(|value| value.into());
+		}
+
+		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+		let left_value = self.query_columns(tree, tree.child(&node, 0), left, right, current_left, middle_left);
+		let right_value = self.query_columns(tree, tree.child(&node, 1), left, right, middle_right, current_right);
+		self.combine(left_value, right_value)
+	}
+
+	fn combine<'a>(&self, left: Option<OwnedRef<'a, T>>, right: Option<OwnedRef<'a, T>>) -> Option<OwnedRef<'a, T>> {
+		match (left, right) {
+			(Some(left), Some(right)) => Some(self.operator.operate(&left, &right).into()),
+			(left, None) => left,
+			(None, right) => right,
+		}
+	}
+
+	/// Updates a value
+	///
+	/// # Panics
+	///
+	/// `position` must be within the grid
+	pub fn update(&mut self, position: (usize, usize), value: T) {
+		let (row, column) = position;
+		assert!(row < self.rows && column < self.columns);
+		let root = self.tree.root();
+		let last = self.rows - 1;
+		self.update_rows(root, position, value, 0, last);
+	}
+
+	fn update_rows(&mut self, node: usize, position: (usize, usize), value: T,
+	               current_top: usize, current_bottom: usize) {
+		let (row, column) = position;
+		if current_top == current_bottom {
+			let columns = self.tree.get_mut(&node).unwrap();
+			let root = columns.root();
+			Self::update_columns(&self.operator, columns, root, column, value, 0, self.columns - 1);
+			return;
+		}
+
+		let (middle_top, middle_bottom) = super::functions::split_range(current_top, current_bottom);
+		let top_child = self.tree.child(&node, 0);
+		let bottom_child = self.tree.child(&node, 1);
+		if row <= middle_top {
+			self.update_rows(top_child, position, value, current_top, middle_top);
+		} else {
+			self.update_rows(bottom_child, position, value, middle_bottom, current_bottom);
+		}
+
+		// Only the column nodes covering `column` have changed
+		let mut values = Vec::new();
+		{
+			let top_columns = self.tree.get(&top_child).unwrap();
+			let bottom_columns = self.tree.get(&bottom_child).unwrap();
+			let (mut current_left, mut current_right) = (0, self.columns - 1);
+			let mut column_node = top_columns.root();
+			loop {
+				let value = self.operator.operate(top_columns.get(&column_node).unwrap(),
+				                            bottom_columns.get(&column_node).unwrap());
+				values.push((column_node, value));
+				if current_left == current_right { break; }
+
+				let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+				if column <= middle_left {
+					column_node = top_columns.child(&column_node, 0);
+					current_right = middle_left;
+				} else {
+					column_node = top_columns.child(&column_node, 1);
+					current_left = middle_right;
+				}
+			}
+		}
+
+		let columns = self.tree.get_mut(&node).unwrap();
+		for (column_node, value) in values {
+			*columns.get_mut(&column_node).unwrap() = value;
+		}
+	}
+
+	fn update_columns(operator: &O, tree: &mut ImplicitTree<T>, node: usize, column: usize, value: T,
+	                  current_left: usize, current_right: usize) {
+		// Same as SegmentTree update_recursively
+		if current_left == current_right {
+			*tree.get_mut(&node).unwrap() = value;
+			return;
+		}
+
+		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+		let left_child = tree.child(&node, 0);
+		let right_child = tree.child(&node, 1);
+		if column <= middle_left {
+			Self::update_columns(operator, tree, left_child, column, value, current_left, middle_left);
+		} else {
+			Self::update_columns(operator, tree, right_child, column, value, middle_right, current_right);
+		}
+
+		let value = operator.operate(tree.get(&left_child).unwrap(), tree.get(&right_child).unwrap());
+		*tree.get_mut(&node).unwrap() = value;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_summation() {
+		use core::ops::Deref;
+		let mut grid = vec![
+			vec![1, 2, 3, 4, 5],
+			vec![6, 7, 8, 9, 10],
+			vec![11, 12, 13, 14, 15],
+		];
+
+		let rows = grid.clone().into_iter().map(|row| row.into_iter());
+		let mut tree = SegmentTree2D::construct_implicit(rows, ::operator::summation());
+		let sum = |grid: &Vec<Vec<i32>>, (top, left): (usize, usize), (bottom, right): (usize, usize)| {
+			grid[top..=bottom].iter().map(|row| row[left..=right].iter().sum::<i32>()).sum::<i32>()
+		};
+
+		for &(position, value) in [((0, 0), 0), ((2, 4), 20), ((1, 2), -8), ((2, 0), 4)].iter() {
+			for bottom in 0..3 {
+				for top in 0..=bottom {
+					for right in 0..5 {
+						for left in 0..=right {
+							let expected = sum(&grid, (top, left), (bottom, right));
+							assert_eq!(*tree.query((top, left), (bottom, right)).deref(), expected);
+						}
+					}
+				}
+			}
+
+			tree.update(position, value);
+			grid[position.0][position.1] = value;
+		}
+	}
+
+	#[test]
+	fn test_minimum() {
+		use core::ops::Deref;
+		let grid = vec![vec![5, 3, 8], vec![2, 9, 7]];
+		let rows = grid.into_iter().map(|row| row.into_iter());
+		let mut tree = SegmentTree2D::construct_implicit(rows, ::operator::minimum());
+		assert_eq!(*tree.query((0, 0), (1, 2)).deref(), 2);
+		assert_eq!(*tree.query((0, 1), (1, 2)).deref(), 3);
+		tree.update((0, 1), 1);
+		assert_eq!(*tree.query((0, 1), (1, 2)).deref(), 1);
+		assert_eq!(*tree.query((1, 1), (1, 2)).deref(), 7);
+	}
+
+	#[test]
+	fn test_ranges() {
+		use core::ops::Deref;
+		let grid = vec![vec![5, 3, 8], vec![2, 9, 7]];
+		let rows = grid.into_iter().map(|row| row.into_iter());
+		let tree = SegmentTree2D::construct_implicit(rows, ::operator::minimum());
+		assert_eq!(*tree.query_ranges(.., ..).deref(), 2);
+		assert_eq!(*tree.query_ranges(.., 1..).deref(), 3);
+		assert_eq!(*tree.query_ranges(1..2, 1..=2).deref(), 7);
+		assert_eq!(tree.try_query_ranges(1..1, ..).err(), Some(Error::EmptyRange));
+		assert_eq!(tree.try_query_ranges(.., 2..4).err(), Some(Error::OutOfBounds { index: 3, length: 3 }));
+	}
+
+	#[test]
+	fn test_empty() {
+		use core::ops::Deref;
+		let rows = Vec::<Vec<i32>>::new().into_iter().map(|row| row.into_iter());
+		let tree = SegmentTree2D::construct_implicit(rows, ::operator::summation());
+		assert_eq!((tree.rows(), tree.columns()), (0, 0));
+
+		let rows = vec![Vec::new(), Vec::new()].into_iter().map(|row: Vec<i32>| row.into_iter());
+		let tree = SegmentTree2D::construct_implicit(rows, ::operator::summation_default());
+		assert_eq!((tree.rows(), tree.columns()), (2, 0));
+		assert_eq!(*tree.query_ranges(.., ..).deref(), 0);
+	}
+}
@@ -0,0 +1,394 @@
+use core::cell::Cell;
+use core::ops::Range;
+use core::ops::RangeBounds;
+use Error;
+use FixedDataSource;
+use super::BackingTree;
+use super::ImplicitTree;
+
+#[derive(Debug, Copy, Clone)]
+struct BeatsNode {
+	sum: i64,
+	maximum: i64,
+	second_maximum: i64,
+	maximum_count: usize,
+	minimum: i64,
+	second_minimum: i64,
+	minimum_count: usize,
+}
+
+impl BeatsNode {
+	fn leaf(value: i64) -> BeatsNode {
+		BeatsNode {
+			sum: value,
+			maximum: value,
+			second_maximum: i64::MIN,
+			maximum_count: 1,
+			minimum: value,
+			second_minimum: i64::MAX,
+			minimum_count: 1,
+		}
+	}
+
+	fn merge(left: &BeatsNode, right: &BeatsNode) -> BeatsNode {
+		use core::cmp::Ordering;
+		let (maximum, second_maximum, maximum_count) = match left.maximum.cmp(&right.maximum) {
+			Ordering::Greater => (left.maximum, left.second_maximum.max(right.maximum), left.maximum_count),
+			Ordering::Less => (right.maximum, right.second_maximum.max(left.maximum), right.maximum_count),
+			Ordering::Equal => (left.maximum, left.second_maximum.max(right.second_maximum),
+			                    left.maximum_count + right.maximum_count),
+		};
+
+		let (minimum, second_minimum, minimum_count) = match left.minimum.cmp(&right.minimum) {
+			Ordering::Less => (left.minimum, left.second_minimum.min(right.minimum), left.minimum_count),
+			Ordering::Greater => (right.minimum, right.second_minimum.min(left.minimum), right.minimum_count),
+			Ordering::Equal => (left.minimum, left.second_minimum.min(right.second_minimum),
+			                    left.minimum_count + right.minimum_count),
+		};
+
+		BeatsNode {
+			sum: left.sum + right.sum,
+			maximum,
+			second_maximum,
+			maximum_count,
+			minimum,
+			second_minimum,
+			minimum_count,
+		}
+	}
+
+	/// Lowers the maximum to `value`
+	///
+	/// `value` must be greater than the second maximum
+	fn apply_minimum(&mut self, value: i64) {
+		if value >= self.maximum { return; }
+		self.sum -= (self.maximum - value) * self.maximum_count as i64;
+		if self.minimum == self.maximum {
+			self.minimum = value;
+		} else if self.second_minimum == self.maximum {
+			self.second_minimum = value;
+		}
+		self.maximum = value;
+	}
+
+	/// Raises the minimum to `value`
+	///
+	/// `value` must be less than the second minimum
+	fn apply_maximum(&mut self, value: i64) {
+		if value <= self.minimum { return; }
+		self.sum += (value - self.minimum) * self.minimum_count as i64;
+		if self.maximum == self.minimum {
+			self.maximum = value;
+		} else if self.second_maximum == self.minimum {
+			self.second_maximum = value;
+		}
+		self.minimum = value;
+	}
+}
+
+/// A variant of `SegmentTree` that allows range clamping
+///
+/// `SegmentTreeBeats` allows every value in a range to be clamped to at most
+/// or at least a given value while supporting range sum, maximum and
+/// minimum queries. Each node tracks its maximum and minimum along with
+/// the second largest and smallest values so that a clamp only has to
+/// descend into a node when it changes more than one distinct value:
+/// - Construct: O(`n`)
+/// - Query: O(`log n`)
+/// - Update: amortised O(`log² n`)
+///
+/// Pending clamps are stored implicitly in the difference between a
+/// node and its children and are sifted down when queried.
+///
+/// Empty ranges have a sum of zero, a maximum of `i64::MIN` and a minimum
+/// of `i64::MAX`. A tree constructed from an empty input has no nodes.
+pub struct SegmentTreeBeats {
+	tree: ImplicitTree<Cell<BeatsNode>>,
+	length: usize,
+}
+
+impl SegmentTreeBeats {
+	pub fn construct(mut values: impl FixedDataSource<i64>) -> SegmentTreeBeats {
+		let mut tree = ImplicitTree::new(super::BINARY_WIDTH);
+		let root = tree.root();
+		let length = values.len();
+		if length > 0 {
+			Self::construct_recursively(&mut values, &mut tree, 0, length - 1, root);
+		}
+
+		SegmentTreeBeats {
+			tree,
+			length,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.length
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.length == 0
+	}
+
+	fn construct_recursively(values: &mut impl FixedDataSource<i64>, tree: &mut ImplicitTree<Cell<BeatsNode>>,
+	                         range_left: usize, range_right: usize, node: usize) {
+		// Same as SegmentTree construct_recursively
+		if range_right == range_left {
+			let value = BeatsNode::leaf(values.next_back().unwrap());
+			unsafe { tree.insert_unchecked(node, Cell::new(value)) };
+			return;
+		}
+
+		let (middle_left, middle_right) = super::functions::split_range(range_left, range_right);
+		let left_child = tree.child(&node, 0);
+		let right_child = tree.child(&node, 1);
+		Self::construct_recursively(values, tree, middle_right, range_right, right_child);
+		Self::construct_recursively(values, tree, range_left, middle_left, left_child);
+		let value = BeatsNode::merge(&tree.get(&left_child).unwrap().get(), &tree.get(&right_child).unwrap().get());
+		unsafe { tree.insert_unchecked(node, Cell::new(value)) };
+	}
+
+	/// Replaces every value in the range with the minimum of it and `value`
+	///
+	/// Nothing is updated if the range is empty.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn update_minimum(&mut self, range: impl RangeBounds<usize>, value: i64) {
+		self.try_update_minimum(range, value).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Replaces every value in the range with the minimum of it and `value`
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_update_minimum(&mut self, range: impl RangeBounds<usize>, value: i64) -> Result<(), Error> {
+		let range = self.bounds(range)?;
+		if range.is_empty() { return Ok(()); }
+		let root = self.tree.root();
+		let last = self.length - 1;
+		self.update_minimum_recursively(root, range.start, range.end - 1, 0, last, value);
+		Ok(())
+	}
+
+	fn update_minimum_recursively(&mut self, node: usize, left: usize, right: usize,
+	                              current_left: usize, current_right: usize, value: i64) {
+		if right < current_left || current_right < left { return; }
+		let mut current_node = self.node(node);
+		if current_node.maximum <= value { return; }
+
+		let in_range = left <= current_left && current_right <= right;
+		if in_range && current_node.second_maximum < value {
+			current_node.apply_minimum(value);
+			self.tree.get(&node).unwrap().set(current_node);
+			return;
+		}
+
+		self.sift_node(node);
+		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+		let (left_child, right_child) = (self.tree.child(&node, 0), self.tree.child(&node, 1));
+		self.update_minimum_recursively(left_child, left, right, current_left, middle_left, value);
+		self.update_minimum_recursively(right_child, left, right, middle_right, current_right, value);
+		self.merge_node(node);
+	}
+
+	/// Replaces every value in the range with the maximum of it and `value`
+	///
+	/// Nothing is updated if the range is empty.
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn update_maximum(&mut self, range: impl RangeBounds<usize>, value: i64) {
+		self.try_update_maximum(range, value).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Replaces every value in the range with the maximum of it and `value`
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_update_maximum(&mut self, range: impl RangeBounds<usize>, value: i64) -> Result<(), Error> {
+		let range = self.bounds(range)?;
+		if range.is_empty() { return Ok(()); }
+		let root = self.tree.root();
+		let last = self.length - 1;
+		self.update_maximum_recursively(root, range.start, range.end - 1, 0, last, value);
+		Ok(())
+	}
+
+	fn update_maximum_recursively(&mut self, node: usize, left: usize, right: usize,
+	                              current_left: usize, current_right: usize, value: i64) {
+		if right < current_left || current_right < left { return; }
+		let mut current_node = self.node(node);
+		if current_node.minimum >= value { return; }
+
+		let in_range = left <= current_left && current_right <= right;
+		if in_range && current_node.second_minimum > value {
+			current_node.apply_maximum(value);
+			self.tree.get(&node).unwrap().set(current_node);
+			return;
+		}
+
+		self.sift_node(node);
+		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+		let (left_child, right_child) = (self.tree.child(&node, 0), self.tree.child(&node, 1));
+		self.update_maximum_recursively(left_child, left, right, current_left, middle_left, value);
+		self.update_maximum_recursively(right_child, left, right, middle_right, current_right, value);
+		self.merge_node(node);
+	}
+
+	/// Get the sum of a range such as `2..5` or `..`
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn query_sum(&self, range: impl RangeBounds<usize>) -> i64 {
+		self.try_query_sum(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the sum of a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_query_sum(&self, range: impl RangeBounds<usize>) -> Result<i64, Error> {
+		Ok(self.query(range)?.map_or(0, |node| node.sum))
+	}
+
+	/// Get the maximum of a range such as `2..5` or `..`
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn query_maximum(&self, range: impl RangeBounds<usize>) -> i64 {
+		self.try_query_maximum(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the maximum of a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_query_maximum(&self, range: impl RangeBounds<usize>) -> Result<i64, Error> {
+		Ok(self.query(range)?.map_or(i64::MIN, |node| node.maximum))
+	}
+
+	/// Get the minimum of a range such as `2..5` or `..`
+	///
+	/// # Panics
+	///
+	/// The range must be within the input array
+	pub fn query_minimum(&self, range: impl RangeBounds<usize>) -> i64 {
+		self.try_query_minimum(range).unwrap_or_else(|error| panic!("{}", error))
+	}
+
+	/// Get the minimum of a range
+	///
+	/// # Errors
+	///
+	/// Returns an error if the range is not within the input array
+	pub fn try_query_minimum(&self, range: impl RangeBounds<usize>) -> Result<i64, Error> {
+		Ok(self.query(range)?.map_or(i64::MAX, |node| node.minimum))
+	}
+
+	/// Returns `None` if the range is empty
+	fn query(&self, range: impl RangeBounds<usize>) -> Result<Option<BeatsNode>, Error> {
+		let range = self.bounds(range)?;
+		if range.is_empty() { return Ok(None); }
+		let root = self.tree.root();
+		Ok(self.query_recursively(root, range.start, range.end - 1, 0, self.length - 1))
+	}
+
+	fn bounds(&self, range: impl RangeBounds<usize>) -> Result<Range<usize>, Error> {
+		::range::bounds(range, self.length)
+	}
+
+	fn query_recursively(&self, node: usize, left: usize, right: usize,
+	                     current_left: usize, current_right: usize) -> Option<BeatsNode> {
+		if right < current_left || current_right < left { return None; }
+		let in_range = left <= current_left && current_right <= right;
+		if in_range { return Some(self.node(node)); }
+
+		self.sift_node(node);
+		let (middle_left, middle_right) = super::functions::split_range(current_left, current_right);
+		let left_child = self.tree.child(&node, 0);
+		let right_child = self.tree.child(&node, 1);
+		let left_value = self.query_recursively(left_child, left, right, current_left, middle_left);
+		let right_value = self.query_recursively(right_child, left, right, middle_right, current_right);
+		match (left_value, right_value) {
+			(Some(left_value), Some(right_value)) => Some(BeatsNode::merge(&left_value, &right_value)),
+			(left_value, None) => left_value,
+			(None, right_value) => right_value,
+		}
+	}
+
+	fn node(&self, node: usize) -> BeatsNode {
+		self.tree.get(&node).unwrap().get()
+	}
+
+	/// Applies the clamps implied by a node to its children
+	fn sift_node(&self, node: usize) {
+		let current_node = self.node(node);
+		for child_index in 0..super::BINARY_WIDTH {
+			if let Some(child) = self.tree.get(&self.tree.child(&node, child_index)) {
+				let mut child_node = child.get();
+				if child_node.maximum > current_node.maximum { child_node.apply_minimum(current_node.maximum); }
+				if child_node.minimum < current_node.minimum { child_node.apply_maximum(current_node.minimum); }
+				child.set(child_node);
+			}
+		}
+	}
+
+	fn merge_node(&self, node: usize) {
+		let left_child = self.node(self.tree.child(&node, 0));
+		let right_child = self.node(self.tree.child(&node, 1));
+		self.tree.get(&node).unwrap().set(BeatsNode::merge(&left_child, &right_child));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test() {
+		let mut data = vec![5, 1, 9, 3, 7, 2, 8, 6, 4, 10, 0];
+		let mut tree = SegmentTreeBeats::construct(data.clone().into_iter());
+		let updates = [(true, 0, 10, 7), (false, 2, 8, 3), (true, 4, 6, 4), (false, 0, 10, 2),
+			(true, 1, 3, 2), (false, 5, 9, 6), (true, 0, 10, 5)];
+
+		for &(minimum, left, right, value) in updates.iter() {
+			if minimum {
+				tree.update_minimum(left..=right, value);
+				data[left..=right].iter_mut().for_each(|element| *element = value.min(*element));
+			} else {
+				tree.update_maximum(left..=right, value);
+				data[left..=right].iter_mut().for_each(|element| *element = value.max(*element));
+			}
+
+			for right in 0..data.len() {
+				for left in 0..=right {
+					let range = &data[left..=right];
+					assert_eq!(tree.query_sum(left..=right), range.iter().sum::<i64>());
+					assert_eq!(tree.query_maximum(left..=right), *range.iter().max().unwrap());
+					assert_eq!(tree.query_minimum(left..=right), *range.iter().min().unwrap());
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_empty() {
+		let mut tree = SegmentTreeBeats::construct(::core::iter::empty());
+		assert!(tree.is_empty());
+		assert_eq!(tree.len(), 0);
+		tree.update_minimum(.., 1);
+		assert_eq!(tree.query_sum(..), 0);
+		assert_eq!(tree.query_maximum(..), i64::MIN);
+		assert_eq!(tree.query_minimum(..), i64::MAX);
+		assert_eq!(tree.try_update_maximum(..=0, 1), Err(Error::OutOfBounds { index: 0, length: 0 }));
+	}
+}
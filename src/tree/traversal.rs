@@ -0,0 +1,131 @@
+use super::BackingTree;
+use Vec;
+
+/// Collects the children of `node` that hold a value
+///
+/// `BackingTree::children` may include children that do not exist such as
+/// the empty slots of an `ImplicitTree`.
+fn existing_children<B>(tree: &B, node: &B::Identifier) -> Vec<B::Identifier> where B: BackingTree {
+	tree.children(node).filter(|child| tree.get(child).is_some()).collect()
+}
+
+/// Visits every node before its children
+pub struct PreOrder<'t, B> where B: 't + BackingTree {
+	tree: &'t B,
+	stack: Vec<B::Identifier>,
+}
+
+impl<'t, B> PreOrder<'t, B> where B: BackingTree {
+	/// Visits the subtree of `start`
+	pub fn new(tree: &'t B, start: B::Identifier) -> PreOrder<'t, B> {
+		let stack = if tree.get(&start).is_some() { vec![start] } else { Vec::new() };
+		PreOrder {
+			tree,
+			stack,
+		}
+	}
+}
+
+impl<'t, B> Iterator for PreOrder<'t, B> where B: BackingTree {
+	type Item = B::Identifier;
+
+	fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+		let node = self.stack.pop()?;
+		self.stack.extend(existing_children(self.tree, &node).into_iter().rev());
+		Some(node)
+	}
+}
+
+/// Visits every node after its children
+pub struct PostOrder<'t, B> where B: 't + BackingTree {
+	tree: &'t B,
+	/// Nodes paired with whether their children have already been added
+	stack: Vec<(B::Identifier, bool)>,
+}
+
+impl<'t, B> PostOrder<'t, B> where B: BackingTree {
+	/// Visits the subtree of `start`
+	pub fn new(tree: &'t B, start: B::Identifier) -> PostOrder<'t, B> {
+		let stack = if tree.get(&start).is_some() { vec![(start, false)] } else { Vec::new() };
+		PostOrder {
+			tree,
+			stack,
+		}
+	}
+}
+
+impl<'t, B> Iterator for PostOrder<'t, B> where B: BackingTree {
+	type Item = B::Identifier;
+
+	fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+		loop {
+			let (node, expanded) = self.stack.pop()?;
+			if expanded { return Some(node); }
+
+			let children = existing_children(self.tree, &node);
+			self.stack.push((node, true));
+			self.stack.extend(children.into_iter().rev().map(|child| (child, false)));
+		}
+	}
+}
+
+/// Visits the nodes level by level from left to right
+pub struct LevelOrder<'t, B> where B: 't + BackingTree {
+	tree: &'t B,
+	/// The remaining nodes of the current level in reverse
+	current: Vec<B::Identifier>,
+	next: Vec<B::Identifier>,
+}
+
+impl<'t, B> LevelOrder<'t, B> where B: BackingTree {
+	/// Visits the subtree of `start`
+	pub fn new(tree: &'t B, start: B::Identifier) -> LevelOrder<'t, B> {
+		let current = if tree.get(&start).is_some() { vec![start] } else { Vec::new() };
+		LevelOrder {
+			tree,
+			current,
+			next: Vec::new(),
+		}
+	}
+}
+
+impl<'t, B> Iterator for LevelOrder<'t, B> where B: BackingTree {
+	type Item = B::Identifier;
+
+	fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+		if self.current.is_empty() {
+			self.current.extend(self.next.drain(..).rev());
+		}
+
+		let node = self.current.pop()?;
+		self.next.extend(existing_children(self.tree, &node));
+		Some(node)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_implicit() {
+		use tree::ImplicitTree;
+
+		// 0 has children 1 and 2, 1 has child 3 and 2 has child 6
+		let mut tree = ImplicitTree::new(2);
+		tree.set_root(0);
+		tree.insert_child(0, 0, 1);
+		tree.insert_child(0, 1, 2);
+		tree.insert_child(1, 0, 3);
+		tree.insert_child(2, 1, 6);
+
+		let values = |nodes: &mut dyn Iterator<Item=usize>| -> Vec<i32> {
+			nodes.map(|node| *tree.get(&node).unwrap()).collect()
+		};
+		assert_eq!(values(&mut PreOrder::new(&tree, 0)), [0, 1, 3, 2, 6]);
+		assert_eq!(values(&mut PostOrder::new(&tree, 0)), [3, 1, 6, 2, 0]);
+		assert_eq!(values(&mut LevelOrder::new(&tree, 0)), [0, 1, 2, 3, 6]);
+		assert_eq!(values(&mut PreOrder::new(&tree, 2)), [2, 6]);
+		assert_eq!(PostOrder::new(&tree, 5).count(), 0);
+	}
+}